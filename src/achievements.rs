@@ -0,0 +1,89 @@
+//! Small goals that are remembered in the save, and announced with a toast when reached.
+
+use crate::render_tooltip;
+use crate::strings::Strings;
+use juquad::widgets::anchor::Anchor;
+use macroquad::math::Vec2;
+use std::collections::VecDeque;
+
+/// Rockets have to chase the cursor for this long without catching it
+pub const SURVIVOR_SECONDS: f32 = 30.0;
+pub const TOAST_SECONDS: f32 = 3.0;
+const TOAST_MARGIN: f32 = 10.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Achievement {
+    /// Clicked the torus button on the other side of the screen
+    ThroughTheWrap,
+    /// Listened to everything the button had to say in the torus
+    SmallTalk,
+    /// Made a rocket hit the button
+    Demolition,
+    Surrender,
+    Survivor,
+    /// Reached every topology of the wrap stage
+    Cartographer,
+}
+
+impl Achievement {
+    /// Stored in the save, so it should never change
+    pub fn id(self) -> &'static str {
+        match self {
+            Achievement::ThroughTheWrap => "through_the_wrap",
+            Achievement::SmallTalk => "small_talk",
+            Achievement::Demolition => "demolition",
+            Achievement::Surrender => "surrender",
+            Achievement::Survivor => "survivor",
+            Achievement::Cartographer => "cartographer",
+        }
+    }
+
+    /// `achievement.<id>` in the string tables
+    pub fn name_key(self) -> String {
+        format!("achievement.{}", self.id())
+    }
+}
+
+/// Announces achievements one at a time
+#[derive(Default)]
+pub struct Toasts {
+    queue: VecDeque<Achievement>,
+    current: Option<(Achievement, f32)>,
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, achievement: Achievement) {
+        self.queue.push_back(achievement);
+    }
+
+    pub fn current(&self) -> Option<Achievement> {
+        self.current.map(|(achievement, _)| achievement)
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        if let Some((_, time)) = &mut self.current {
+            *time += dt;
+            if *time >= TOAST_SECONDS {
+                self.current = None;
+            }
+        }
+        if self.current.is_none() {
+            self.current = self.queue.pop_front().map(|achievement| (achievement, 0.0));
+        }
+    }
+
+    pub fn render(&self, strings: &Strings, screen: Vec2) {
+        if let Some(achievement) = self.current() {
+            let text = format!(
+                "{}: {}",
+                strings.get("achievement_unlocked"),
+                strings.get(&achievement.name_key())
+            );
+            render_tooltip(&text, Anchor::top_center(screen.x * 0.5, TOAST_MARGIN));
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! What the player wants to do, decoupled from which keys or mouse buttons do it.
+
+use crate::key_names::{key_from_name, key_name, ALL_KEY_CODES};
+use crate::save::SaveBackend;
+use juquad::input::input_trait::InputTrait;
+use macroquad::input::{KeyCode, MouseButton};
+
+pub const BINDINGS_PATH: &str = "press-to-start-bindings.txt";
+const MOUSE_PREFIX: &str = "Mouse";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Restart,
+    Pause,
+    /// Prints positions to the terminal
+    DebugDump,
+    SwitchLanguage,
+    Settings,
+    /// Shows the force field, hitboxes and timers on top of any stage
+    DevOverlay,
+}
+
+pub const ALL_ACTIONS: [Action; 7] = [
+    Action::Quit,
+    Action::Restart,
+    Action::Pause,
+    Action::DebugDump,
+    Action::SwitchLanguage,
+    Action::Settings,
+    Action::DevOverlay,
+];
+
+impl Action {
+    /// Used in the bindings file, and as `action.<id>` in the string tables
+    pub fn id(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Restart => "restart",
+            Action::Pause => "pause",
+            Action::DebugDump => "debug_dump",
+            Action::SwitchLanguage => "switch_language",
+            Action::Settings => "settings",
+            Action::DevOverlay => "dev_overlay",
+        }
+    }
+    pub fn from_id(id: &str) -> Option<Self> {
+        ALL_ACTIONS.into_iter().find(|action| action.id() == id)
+    }
+    pub fn name_key(self) -> String {
+        format!("action.{}", self.id())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyCode),
+    /// The left button is not allowed, because it is how the player clicks on everything
+    Mouse(MouseButton),
+}
+
+/// Mouse buttons that can be bound to actions
+pub const BINDABLE_MOUSE_BUTTONS: [MouseButton; 2] = [MouseButton::Right, MouseButton::Middle];
+
+impl Binding {
+    pub fn name(self) -> String {
+        match self {
+            Binding::Key(key) => key_name(key),
+            Binding::Mouse(button) => format!("{}{:?}", MOUSE_PREFIX, button),
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.strip_prefix(MOUSE_PREFIX) {
+            Some(button) => BINDABLE_MOUSE_BUTTONS
+                .into_iter()
+                .find(|b| format!("{:?}", b) == button)
+                .map(Binding::Mouse),
+            None => key_from_name(name).map(Binding::Key),
+        }
+    }
+    pub fn is_pressed(self, input: &Box<dyn InputTrait>) -> bool {
+        match self {
+            Binding::Key(key) => input.is_key_pressed(key),
+            Binding::Mouse(button) => input.is_mouse_button_pressed(button),
+        }
+    }
+}
+
+/// The binding that was pressed in this step, if any. Used when rebinding.
+pub fn pressed_binding(input: &Box<dyn InputTrait>) -> Option<Binding> {
+    let key = ALL_KEY_CODES
+        .iter()
+        .find(|key| input.is_key_pressed(**key))
+        .map(|key| Binding::Key(*key));
+    key.or_else(|| {
+        BINDABLE_MOUSE_BUTTONS
+            .into_iter()
+            .find(|button| input.is_mouse_button_pressed(*button))
+            .map(Binding::Mouse)
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bindings {
+    /// In the order of `ALL_ACTIONS`
+    bindings: Vec<(Action, Vec<Binding>)>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let key = |action, key| (action, vec![Binding::Key(key)]);
+        Self {
+            bindings: vec![
+                key(Action::Quit, KeyCode::Escape),
+                key(Action::Restart, KeyCode::R),
+                key(Action::Pause, KeyCode::P),
+                key(Action::DebugDump, KeyCode::Space),
+                key(Action::SwitchLanguage, KeyCode::L),
+                key(Action::Settings, KeyCode::F1),
+                key(Action::DevOverlay, KeyCode::F3),
+            ],
+        }
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map_or(&[], |(_, bindings)| bindings.as_slice())
+    }
+
+    pub fn is_pressed(&self, action: Action, input: &Box<dyn InputTrait>) -> bool {
+        self.get(action)
+            .iter()
+            .any(|binding| binding.is_pressed(input))
+    }
+
+    /// The other action that already uses this binding
+    pub fn conflict(&self, action: Action, binding: Binding) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(a, bindings)| *a != action && bindings.contains(&binding))
+            .map(|(a, _)| *a)
+    }
+
+    /// Replaces the bindings of `action`, unless another action uses the same binding
+    pub fn rebind(&mut self, action: Action, binding: Binding) -> Result<(), Action> {
+        if let Some(other) = self.conflict(action, binding) {
+            return Err(other);
+        }
+        match self.bindings.iter_mut().find(|(a, _)| *a == action) {
+            Some((_, bindings)) => *bindings = vec![binding],
+            None => self.bindings.push((action, vec![binding])),
+        }
+        Ok(())
+    }
+
+    /// `action = binding, binding` per line
+    pub fn serialize(&self) -> String {
+        let mut text = String::new();
+        for (action, bindings) in &self.bindings {
+            let names = bindings.iter().map(|b| b.name()).collect::<Vec<_>>();
+            text.push_str(&format!("{} = {}\n", action.id(), names.join(", ")));
+        }
+        text
+    }
+
+    /// Actions missing in the text keep their default bindings. Two actions with the same
+    /// binding are an error, because one of them would never happen.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: String| format!("line {}: {}", i + 1, message);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (id, names) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `action = binding, binding`".to_string()))?;
+            let action = Action::from_id(id.trim())
+                .ok_or_else(|| error(format!("unknown action {}", id.trim())))?;
+            let bindings = names
+                .split(',')
+                .map(|name| name.trim())
+                .filter(|name| !name.is_empty())
+                .map(|name| {
+                    Binding::from_name(name)
+                        .ok_or_else(|| error(format!("unknown binding {}", name)))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            for (a, existing) in parsed.bindings.iter_mut() {
+                if *a == action {
+                    *existing = bindings.clone();
+                }
+            }
+        }
+        for (action, bindings) in &parsed.bindings {
+            for binding in bindings {
+                if let Some(other) = parsed.conflict(*action, *binding) {
+                    return Err(format!(
+                        "{} is bound to both {} and {}",
+                        binding.name(),
+                        action.id(),
+                        other.id()
+                    ));
+                }
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Uses the defaults if there is no file yet or it has a mistake
+    pub fn load(backend: &dyn SaveBackend) -> Self {
+        match backend.read() {
+            Ok(Some(text)) => Self::parse(&text).unwrap_or_else(|e| {
+                println!(
+                    "could not parse the key bindings, using the defaults: {}",
+                    e
+                );
+                Self::default()
+            }),
+            Ok(None) => Self::default(),
+            Err(e) => {
+                println!("could not read the key bindings, using the defaults: {}", e);
+                Self::default()
+            }
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! Sound effects synthesized at startup, and the mixer that decides which ones play and how loud.
+//!
+//! Stages only ask the `Mixer` in `Shared` to play effects. Whoever owns the window flushes it
+//! into an `AudioBackend`, so the simulation stays headless.
+
+use crate::synth::{
+    encode_wav, fade_edges, normalize, render, saw, sine, square, Envelope, LowPass, Noise,
+    Oscillator,
+};
+use macroquad::audio::{load_sound_from_bytes, play_sound, PlaySoundParams, Sound};
+use std::collections::HashMap;
+
+const PEAK: f32 = 0.8;
+const EDGE_FADE_SECONDS: f32 = 0.003;
+const THRUST_SECONDS: f32 = 0.4;
+/// Older effects are dropped past this many, like when nothing flushes the mixer in headless runs
+pub const MAX_QUEUED: usize = 32;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SoundEffect {
+    /// The button being pushed away from the mouse
+    Whoosh,
+    /// Loops while a rocket flies
+    Thrust,
+    Explosion,
+    DeathSting,
+    /// A dialog line appearing
+    Blip,
+}
+
+pub const ALL_SOUND_EFFECTS: [SoundEffect; 5] = [
+    SoundEffect::Whoosh,
+    SoundEffect::Thrust,
+    SoundEffect::Explosion,
+    SoundEffect::DeathSting,
+    SoundEffect::Blip,
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Category {
+    Effects,
+    Dialog,
+}
+
+impl SoundEffect {
+    pub fn category(self) -> Category {
+        match self {
+            SoundEffect::Blip => Category::Dialog,
+            _ => Category::Effects,
+        }
+    }
+
+    /// Requests closer than this are dropped, so stages can ask for an effect every step
+    pub fn min_interval(self) -> f32 {
+        match self {
+            SoundEffect::Whoosh => 0.3,
+            SoundEffect::Thrust => THRUST_SECONDS,
+            SoundEffect::Explosion | SoundEffect::DeathSting => 0.0,
+            SoundEffect::Blip => 0.05,
+        }
+    }
+
+    pub fn synthesize(self, sample_rate: u32) -> Vec<f32> {
+        let mut noise = Noise::new(0x5eed + self as u32);
+        let mut low_pass = LowPass::new();
+        let mut oscillator = Oscillator::new();
+        let mut samples = match self {
+            SoundEffect::Whoosh => {
+                let seconds = 0.3;
+                let envelope = Envelope {
+                    attack: 0.12,
+                    decay: 0.08,
+                };
+                render(seconds, sample_rate, |t| {
+                    let sweep = sine(t / seconds * 0.5);
+                    let cutoff = 400.0 + 2000.0 * sweep;
+                    low_pass.filter(noise.sample(), cutoff, sample_rate) * envelope.gain(t)
+                })
+            }
+            SoundEffect::Thrust => render(THRUST_SECONDS, sample_rate, |_| {
+                let rumble = low_pass.filter(noise.sample(), 300.0, sample_rate);
+                rumble * 0.8 + saw(oscillator.advance(55.0, sample_rate)) * 0.1
+            }),
+            SoundEffect::Explosion => {
+                let envelope = Envelope {
+                    attack: 0.005,
+                    decay: 0.4,
+                };
+                render(1.5, sample_rate, |t| {
+                    let cutoff = 3000.0 * (-t * 3.0).exp() + 100.0;
+                    let debris = low_pass.filter(noise.sample(), cutoff, sample_rate);
+                    let thump = sine(oscillator.advance(60.0 - 30.0 * t.min(1.0), sample_rate));
+                    (debris + thump * 0.5) * envelope.gain(t)
+                })
+            }
+            SoundEffect::DeathSting => {
+                // three falling notes, the last one held
+                let notes = [(0.0, 440.0), (0.2, 349.23), (0.4, 293.66)];
+                render(1.2, sample_rate, |t| {
+                    let (start, frequency) = notes
+                        .iter()
+                        .rev()
+                        .find(|(start, _)| t >= *start)
+                        .copied()
+                        .unwrap_or(notes[0]);
+                    let envelope = Envelope {
+                        attack: 0.01,
+                        decay: if start < 0.4 { 0.15 } else { 0.4 },
+                    };
+                    let phase = oscillator.advance(frequency, sample_rate);
+                    (square(phase) * 0.4 + sine(phase) * 0.6) * envelope.gain(t - start)
+                })
+            }
+            SoundEffect::Blip => {
+                let envelope = Envelope {
+                    attack: 0.002,
+                    decay: 0.02,
+                };
+                render(0.06, sample_rate, |t| {
+                    square(oscillator.advance(1320.0, sample_rate)) * envelope.gain(t)
+                })
+            }
+        };
+        normalize(&mut samples, PEAK);
+        fade_edges(
+            &mut samples,
+            (EDGE_FADE_SECONDS * sample_rate as f32) as usize,
+        );
+        samples
+    }
+}
+
+/// Volumes in [0, 1]. The volume of a category is multiplied by the master volume.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Volumes {
+    pub master: f32,
+    pub effects: f32,
+    pub dialog: f32,
+}
+
+impl Default for Volumes {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            effects: 1.0,
+            dialog: 1.0,
+        }
+    }
+}
+
+impl Volumes {
+    pub fn get(&self, category: Category) -> f32 {
+        let volume = match category {
+            Category::Effects => self.effects,
+            Category::Dialog => self.dialog,
+        };
+        volume * self.master
+    }
+}
+
+/// Every effect synthesized once
+pub struct SoundBank {
+    pub sample_rate: u32,
+    clips: HashMap<SoundEffect, Vec<f32>>,
+}
+
+impl SoundBank {
+    pub fn synthesize(sample_rate: u32) -> Self {
+        let clips = ALL_SOUND_EFFECTS
+            .into_iter()
+            .map(|effect| (effect, effect.synthesize(sample_rate)))
+            .collect();
+        Self { sample_rate, clips }
+    }
+
+    pub fn clip(&self, effect: SoundEffect) -> &[f32] {
+        &self.clips[&effect]
+    }
+
+    /// Adds up the clips that start at the given sample, at the given volume, like the audio
+    /// device does with overlapping sounds. Useful to check the levels offline.
+    pub fn mix(&self, plays: &[(usize, SoundEffect, f32)]) -> Vec<f32> {
+        let len = plays
+            .iter()
+            .map(|(start, effect, _)| start + self.clip(*effect).len())
+            .max()
+            .unwrap_or(0);
+        let mut mixed = vec![0.0; len];
+        for (start, effect, volume) in plays {
+            for (i, sample) in self.clip(*effect).iter().enumerate() {
+                mixed[start + i] += sample * volume;
+            }
+        }
+        for sample in &mut mixed {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+        mixed
+    }
+}
+
+pub trait AudioBackend {
+    /// Starts playing the effect once, on top of whatever is already playing
+    fn play(&mut self, effect: SoundEffect, volume: f32);
+}
+
+/// Plays nothing, but remembers what it was asked to play. Used in tests, and when the sounds
+/// could not be loaded.
+#[derive(Default)]
+pub struct NullBackend {
+    pub played: Vec<(SoundEffect, f32)>,
+}
+
+impl AudioBackend for NullBackend {
+    fn play(&mut self, effect: SoundEffect, volume: f32) {
+        self.played.push((effect, volume));
+    }
+}
+
+pub struct MacroquadBackend {
+    sounds: HashMap<SoundEffect, Sound>,
+}
+
+impl MacroquadBackend {
+    pub async fn load(bank: &SoundBank) -> Result<Self, String> {
+        let mut sounds = HashMap::new();
+        for effect in ALL_SOUND_EFFECTS {
+            let wav = encode_wav(bank.clip(effect), bank.sample_rate);
+            let sound = load_sound_from_bytes(&wav)
+                .await
+                .map_err(|e| format!("{:?}: {}", effect, e))?;
+            sounds.insert(effect, sound);
+        }
+        Ok(Self { sounds })
+    }
+}
+
+impl AudioBackend for MacroquadBackend {
+    fn play(&mut self, effect: SoundEffect, volume: f32) {
+        let params = PlaySoundParams {
+            looped: false,
+            volume,
+        };
+        play_sound(self.sounds[&effect], params);
+    }
+}
+
+/// Collects the effects that the stages want to play until the backend takes them
+#[derive(Default)]
+pub struct Mixer {
+    queued: Vec<SoundEffect>,
+    /// Seconds until each effect can be requested again
+    cooldowns: HashMap<SoundEffect, f32>,
+}
+
+impl Mixer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn play(&mut self, effect: SoundEffect) {
+        if self.cooldowns.get(&effect).copied().unwrap_or(0.0) <= 0.0 {
+            if self.queued.len() == MAX_QUEUED {
+                self.queued.remove(0);
+            }
+            self.queued.push(effect);
+            self.cooldowns.insert(effect, effect.min_interval());
+        }
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        for cooldown in self.cooldowns.values_mut() {
+            *cooldown -= dt;
+        }
+    }
+
+    pub fn queued(&self) -> &[SoundEffect] {
+        &self.queued
+    }
+
+    /// Sends the queued effects to the backend at the volume of their category. Muted ones are
+    /// dropped.
+    pub fn flush(&mut self, volumes: &Volumes, backend: &mut dyn AudioBackend) {
+        for effect in self.queued.drain(..) {
+            let volume = volumes.get(effect.category());
+            if volume > 0.0 {
+                backend.play(effect, volume);
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::replay::{button_bit, FrameInput, InputFrame};
+use crate::stages::driver::{Shared, Simulation};
+use crate::timestep::FIXED_DT;
+use juquad::input::input_trait::InputTrait;
+use juquad::widgets::Widget;
+use macroquad::input::{KeyCode, MouseButton};
+use macroquad::math::Vec2;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BotAction {
+    /// Jumps the pointer there, like a very fast flick
+    MoveTo(Vec2),
+    /// Glides toward the center of the button, `speed` pixels per step, until reaching it
+    ChaseButton {
+        speed: f32,
+    },
+    /// Presses and releases the left mouse button, taking 2 steps
+    Click,
+    Press(KeyCode),
+    /// Does nothing for this many steps
+    Wait(usize),
+}
+
+/// A fake player that follows a script, looking at the game between steps
+pub struct Bot {
+    script: VecDeque<BotAction>,
+    frame: Rc<RefCell<InputFrame>>,
+    clicking: bool,
+    waited: usize,
+}
+
+impl Bot {
+    pub fn new(mouse: Vec2, script: Vec<BotAction>) -> Self {
+        let frame = InputFrame {
+            mouse,
+            ..Default::default()
+        };
+        Self {
+            script: script.into(),
+            frame: Rc::new(RefCell::new(frame)),
+            clicking: false,
+            waited: 0,
+        }
+    }
+
+    pub fn input(&self) -> Box<dyn InputTrait> {
+        Box::new(FrameInput::new(self.frame.clone()))
+    }
+
+    pub fn mouse(&self) -> Vec2 {
+        self.frame.borrow().mouse
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.script.is_empty()
+    }
+
+    /// Decides the input for the next step
+    pub fn think(&mut self, shared: &Shared) {
+        let mut frame = self.frame.borrow_mut();
+        // events only last one step
+        frame.buttons_pressed = 0;
+        frame.buttons_released = 0;
+        frame.keys_pressed.clear();
+        frame.screen = shared.screen;
+        let left = button_bit(MouseButton::Left);
+        let done = match self.script.front().copied() {
+            None => return,
+            Some(BotAction::MoveTo(pos)) => {
+                frame.mouse = pos;
+                true
+            }
+            Some(BotAction::ChaseButton { speed }) => {
+                let target = shared.button.rect().center();
+                let to_target = target - frame.mouse;
+                if to_target.length() <= speed {
+                    frame.mouse = target;
+                    true
+                } else {
+                    frame.mouse += to_target.normalize() * speed;
+                    false
+                }
+            }
+            Some(BotAction::Click) => {
+                if self.clicking {
+                    frame.buttons_down &= !left;
+                    frame.buttons_released |= left;
+                } else {
+                    frame.buttons_down |= left;
+                    frame.buttons_pressed |= left;
+                }
+                self.clicking = !self.clicking;
+                !self.clicking
+            }
+            Some(BotAction::Press(key)) => {
+                frame.keys_pressed.push(key);
+                true
+            }
+            Some(BotAction::Wait(steps)) => {
+                self.waited += 1;
+                if self.waited >= steps {
+                    self.waited = 0;
+                    true
+                } else {
+                    false
+                }
+            }
+        };
+        if done {
+            self.script.pop_front();
+        }
+    }
+}
+
+/// Steps the simulation with the bot until its script is over, the game exits, or `max_steps`.
+/// Returns how many steps ran.
+pub fn run_bot(simulation: &mut Simulation, bot: &mut Bot, max_steps: usize) -> usize {
+    let input = bot.input();
+    for step in 0..max_steps {
+        if bot.is_done() {
+            return step;
+        }
+        bot.think(&simulation.shared);
+        if !simulation.step(&input, FIXED_DT) {
+            return step + 1;
+        }
+    }
+    max_steps
+}
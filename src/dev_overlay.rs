@@ -0,0 +1,150 @@
+//! Drawn on top of any stage while developing: how the fields would push the button from every
+//! spot, the hitboxes that collisions use, and the state of the stage.
+
+use crate::dialog::DialogRunner;
+use crate::forces::Probe;
+use crate::geometry::{draw_shape_lines, Shape};
+use crate::stages::driver::Shared;
+use crate::{FONT_SIZE, STYLE};
+use juquad::draw::draw_rect;
+use juquad::widgets::anchor::{Horizontal, Layout, Vertical};
+use juquad::widgets::anchorer::Anchorer;
+use juquad::widgets::Widget;
+use macroquad::color::{Color, BLUE, DARKGREEN, GRAY, LIGHTGRAY, ORANGE, RED};
+use macroquad::math::{vec2, Rect, Vec2};
+use macroquad::prelude::draw_line;
+
+/// Arrows in each row and column of the force field
+const FIELD_ARROWS: usize = 24;
+/// In pixels per second. Faster fields get arrows as long as this one.
+const FIELD_MAX_SPEED: f32 = 6000.0;
+const HITBOX_THICKNESS: f32 = 2.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Hitbox {
+    Button,
+    /// A copy of the button seen through an edge
+    Ghost,
+    Mouse,
+    Rocket,
+    /// A rocket that can't hurt
+    Decoy,
+}
+
+impl Hitbox {
+    pub fn color(self) -> Color {
+        match self {
+            Hitbox::Button => DARKGREEN,
+            Hitbox::Ghost => ORANGE,
+            Hitbox::Mouse => BLUE,
+            Hitbox::Rocket => RED,
+            Hitbox::Decoy => GRAY,
+        }
+    }
+}
+
+/// Where the fields of a stage push the button from
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldView {
+    pub stage: &'static str,
+    /// The mouse, and wherever else it is seen from, like through the edges of a torus
+    pub mice: Vec<Vec2>,
+    pub home: Vec2,
+    /// Seconds since the stage started
+    pub time: f32,
+}
+
+#[derive(Default)]
+pub struct DevOverlay {
+    pub field: Option<FieldView>,
+    pub hitboxes: Vec<(Hitbox, Shape)>,
+    pub lines: Vec<String>,
+}
+
+impl DevOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(&mut self, view: FieldView) {
+        self.field = Some(view);
+    }
+
+    pub fn hitbox(&mut self, hitbox: Hitbox, shape: Shape) {
+        self.hitboxes.push((hitbox, shape));
+    }
+
+    pub fn line(&mut self, text: impl Into<String>) {
+        self.lines.push(text.into());
+    }
+
+    /// Which line is being said and for how long
+    pub fn dialog(&mut self, dialog: &DialogRunner) {
+        match dialog.current_id() {
+            Some(id) => self.line(format!("dialog: {} for {:.1}s", id, dialog.line_time())),
+            None => self.line("dialog: finished"),
+        }
+    }
+
+    /// In pixels per second, if the button was centered at `pos`
+    pub fn field_velocity(&self, shared: &Shared, pos: Vec2) -> Option<Vec2> {
+        let view = self.field.as_ref()?;
+        let bounds = Rect::new(0.0, 0.0, shared.screen.x, shared.screen.y);
+        let velocity = view.mice.iter().fold(Vec2::ZERO, |sum, mouse| {
+            let probe = Probe {
+                pos,
+                mouse: *mouse,
+                home: view.home,
+                bounds,
+                time: view.time,
+            };
+            sum + shared.forces.velocity(view.stage, &probe)
+        });
+        Some(velocity)
+    }
+
+    pub fn render(&self, shared: &Shared) {
+        if self.field.is_some() {
+            self.render_field(shared);
+        }
+        for (hitbox, shape) in &self.hitboxes {
+            draw_shape_lines(shape, HITBOX_THICKNESS, hitbox.color());
+        }
+        let layout = Layout::Vertical {
+            direction: Vertical::Bottom,
+            alignment: Horizontal::Right,
+        };
+        let mut anchorer = Anchorer::new_pos(layout, vec2(shared.screen.x, 0.0), 0.0);
+        for line in &self.lines {
+            let text_rect = anchorer.new_text(line, FONT_SIZE);
+            draw_rect(text_rect.rect(), LIGHTGRAY);
+            text_rect.render_default(&STYLE.at_rest);
+        }
+    }
+
+    /// An arrow at each spot of a grid, longer and redder the faster the button would move
+    fn render_field(&self, shared: &Shared) {
+        let cell = shared.screen / FIELD_ARROWS as f32;
+        for i_x in 0..FIELD_ARROWS {
+            for i_y in 0..FIELD_ARROWS {
+                let pos = vec2(i_x as f32 + 0.5, i_y as f32 + 0.5) * cell;
+                let velocity = self.field_velocity(shared, pos).unwrap_or_default();
+                let speed = velocity.length();
+                let normalized = ((1.0 + speed).log10() / (1.0 + FIELD_MAX_SPEED).log10()).min(1.0);
+                let color = Color::new(normalized, 0.2, 1.0 - normalized, 0.8);
+                let arrow = velocity.normalize_or_zero() * cell.min_element() * 0.9 * normalized;
+                draw_arrow(pos - arrow * 0.5, arrow, color);
+            }
+        }
+    }
+}
+
+fn draw_arrow(start: Vec2, arrow: Vec2, color: Color) {
+    let end = start + arrow;
+    draw_line(start.x, start.y, end.x, end.y, 1.0, color);
+    let back = -arrow * 0.3;
+    for side in [vec2(back.y, -back.x), vec2(-back.y, back.x)] {
+        let barb = end + (back + side) * 0.5;
+        draw_line(end.x, end.y, barb.x, barb.y, 1.0, color);
+    }
+}
@@ -0,0 +1,347 @@
+//! Lines that the button says, loaded from a text file so that they can be changed without
+//! touching the code. See `assets/dialogs.txt` for the format.
+
+use crate::strings::Strings;
+use crate::{create_tooltip_anchor, render_tooltip};
+use juquad::widgets::anchor::Anchor;
+use macroquad::math::{Rect, Vec2};
+use std::collections::HashMap;
+
+pub const DIALOGS_PATH: &str = "assets/dialogs.txt";
+pub const DEFAULT_DIALOGS: &str = include_str!("../assets/dialogs.txt");
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Condition {
+    /// The speaker is under the mouse
+    Hover,
+    /// The speaker was clicked
+    Click,
+    /// The line was shown for its whole duration
+    Done,
+    /// This many seconds without hovering or clicking the speaker
+    Idle(f64),
+    /// This many seconds since the line started
+    After(f64),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Target {
+    Line(usize),
+    End,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Speaker {
+    Button,
+    Center,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Branch {
+    /// All of them have to be true at the same time
+    pub conditions: Vec<Condition>,
+    pub target: Target,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    pub id: String,
+    /// None means the line stays until a branch is taken
+    pub duration: Option<f64>,
+    pub speaker: Speaker,
+    pub text: String,
+    pub branches: Vec<Branch>,
+}
+
+/// The lines of one stage. The first line is where it starts.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DialogScript {
+    /// The section in the file, which is also the middle part of the translation keys
+    pub name: String,
+    pub lines: Vec<Line>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dialogs {
+    scripts: HashMap<String, DialogScript>,
+}
+
+impl Dialogs {
+    pub fn embedded() -> Self {
+        Self::parse(DEFAULT_DIALOGS).expect("the embedded dialogs should be valid")
+    }
+
+    pub fn runner(&self, name: &str) -> DialogRunner {
+        match self.scripts.get(name) {
+            Some(script) => DialogRunner::new(script.clone()),
+            None => {
+                println!("no dialogs for {}", name);
+                DialogRunner::new(DialogScript::default())
+            }
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut scripts = HashMap::new();
+        let mut section: Option<(String, Vec<Line>, Vec<Vec<UnresolvedBranch>>)> = None;
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: String| format!("line {}: {}", i + 1, message);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if let Some((name, lines, branches)) = section.take() {
+                    scripts.insert(name.clone(), resolve(name, lines, branches)?);
+                }
+                section = Some((name.to_string(), Vec::new(), Vec::new()));
+                continue;
+            }
+            let (_, lines, branches) = section
+                .as_mut()
+                .ok_or_else(|| error("expected a [section] first".to_string()))?;
+            if let Some(rest) = line.strip_prefix("line ") {
+                lines.push(parse_line(rest).map_err(error)?);
+                branches.push(Vec::new());
+            } else if let Some(rest) = line.strip_prefix("on ") {
+                let branch = parse_branch(rest).map_err(error)?;
+                branches
+                    .last_mut()
+                    .ok_or_else(|| error("'on' before any 'line'".to_string()))?
+                    .push(branch);
+            } else {
+                return Err(error(format!("unknown statement: {}", line)));
+            }
+        }
+        if let Some((name, lines, branches)) = section.take() {
+            scripts.insert(name.clone(), resolve(name, lines, branches)?);
+        }
+        Ok(Self { scripts })
+    }
+}
+
+/// The conditions and the id of the target line, which might not be parsed yet
+type UnresolvedBranch = (Vec<Condition>, String);
+
+/// `<id> <seconds|forever> [@button|@center] "<text>"`
+fn parse_line(rest: &str) -> Result<Line, String> {
+    let quote = rest
+        .find('"')
+        .ok_or_else(|| "expected the text in quotes".to_string())?;
+    let text = rest[quote..]
+        .strip_prefix('"')
+        .and_then(|t| t.strip_suffix('"'))
+        .ok_or_else(|| "the text should end with a quote".to_string())?;
+    let words = rest[..quote].split_whitespace().collect::<Vec<_>>();
+    let (id, duration, speaker) = match words.as_slice() {
+        [id, duration] => (id, duration, Speaker::Button),
+        [id, duration, speaker] => (id, duration, parse_speaker(speaker)?),
+        _ => return Err("expected: line <id> <seconds|forever> [@speaker] \"text\"".to_string()),
+    };
+    let duration = if *duration == "forever" {
+        None
+    } else {
+        Some(parse_seconds(duration)?)
+    };
+    Ok(Line {
+        id: id.to_string(),
+        duration,
+        speaker,
+        text: text.to_string(),
+        branches: Vec::new(),
+    })
+}
+
+fn parse_speaker(word: &str) -> Result<Speaker, String> {
+    match word {
+        "@button" => Ok(Speaker::Button),
+        "@center" => Ok(Speaker::Center),
+        _ => Err(format!("unknown speaker {}", word)),
+    }
+}
+
+fn parse_seconds(word: &str) -> Result<f64, String> {
+    word.parse::<f64>().map_err(|e| format!("{}: {}", word, e))
+}
+
+/// `<condition> [<condition>...] goto <id|end>`
+fn parse_branch(rest: &str) -> Result<UnresolvedBranch, String> {
+    let (conditions, target) = rest
+        .split_once(" goto ")
+        .ok_or_else(|| "expected: on <conditions> goto <id|end>".to_string())?;
+    let mut words = conditions.split_whitespace();
+    let mut parsed = Vec::new();
+    while let Some(word) = words.next() {
+        let mut seconds = || {
+            let word = words
+                .next()
+                .ok_or_else(|| "expected the seconds".to_string())?;
+            parse_seconds(word)
+        };
+        parsed.push(match word {
+            "hover" => Condition::Hover,
+            "click" => Condition::Click,
+            "done" => Condition::Done,
+            "idle" => Condition::Idle(seconds()?),
+            "after" => Condition::After(seconds()?),
+            _ => return Err(format!("unknown condition {}", word)),
+        });
+    }
+    Ok((parsed, target.trim().to_string()))
+}
+
+fn resolve(
+    name: String,
+    mut lines: Vec<Line>,
+    branches: Vec<Vec<UnresolvedBranch>>,
+) -> Result<DialogScript, String> {
+    let ids = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| (line.id.clone(), i))
+        .collect::<HashMap<_, _>>();
+    for (line, line_branches) in lines.iter_mut().zip(branches) {
+        for (conditions, target) in line_branches {
+            let target =
+                if target == "end" {
+                    Target::End
+                } else {
+                    Target::Line(*ids.get(&target).ok_or_else(|| {
+                        format!("line {} goes to unknown line {}", line.id, target)
+                    })?)
+                };
+            line.branches.push(Branch { conditions, target });
+        }
+    }
+    Ok(DialogScript { name, lines })
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DialogEvents {
+    pub hovered: bool,
+    pub clicked: bool,
+}
+
+pub struct DialogRunner {
+    script: DialogScript,
+    current: Option<usize>,
+    /// The line that was visible after the last step
+    shown: Option<usize>,
+    line_time: f64,
+    idle_time: f64,
+}
+
+impl DialogRunner {
+    pub fn new(script: DialogScript) -> Self {
+        let current = if script.lines.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+        Self {
+            script,
+            current,
+            shown: None,
+            line_time: 0.0,
+            idle_time: 0.0,
+        }
+    }
+
+    /// Returns true when a line with text appears
+    pub fn step(&mut self, events: DialogEvents, dt: f32) -> bool {
+        let line = match self.current {
+            Some(current) => &self.script.lines[current],
+            None => return false,
+        };
+        self.line_time += dt as f64;
+        if events.hovered || events.clicked {
+            self.idle_time = 0.0;
+        } else {
+            self.idle_time += dt as f64;
+        }
+        let done = line
+            .duration
+            .is_some_and(|duration| self.line_time >= duration);
+        let holds = |condition: &Condition| match condition {
+            Condition::Hover => events.hovered,
+            Condition::Click => events.clicked,
+            Condition::Done => done,
+            Condition::Idle(seconds) => self.idle_time >= *seconds,
+            Condition::After(seconds) => self.line_time >= *seconds,
+        };
+        let taken = line
+            .branches
+            .iter()
+            .find(|branch| branch.conditions.iter().all(holds))
+            .map(|branch| branch.target);
+        match taken {
+            Some(Target::Line(next)) => {
+                self.current = Some(next);
+                self.line_time = 0.0;
+                self.idle_time = 0.0;
+            }
+            Some(Target::End) => self.current = None,
+            None => {}
+        }
+        let shown = self.visible_line().and(self.current);
+        let appeared = shown.is_some() && shown != self.shown;
+        self.shown = shown;
+        appeared
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current.is_none()
+    }
+
+    pub fn current_id(&self) -> Option<&str> {
+        self.current.map(|i| self.script.lines[i].id.as_str())
+    }
+
+    /// The line to show right now, if any
+    pub fn visible_line(&self) -> Option<&Line> {
+        let line = &self.script.lines[self.current?];
+        let visible = !line
+            .duration
+            .is_some_and(|duration| self.line_time >= duration);
+        if visible && !line.text.is_empty() {
+            Some(line)
+        } else {
+            None
+        }
+    }
+
+    pub fn line_time(&self) -> f64 {
+        self.line_time
+    }
+}
+
+/// `dialog.<stage>.<line id>` in the string tables
+pub fn translation_key(script: &DialogScript, line: &Line) -> String {
+    format!("dialog.{}.{}", script.name, line.id)
+}
+
+pub fn render_dialog(runner: &DialogRunner, strings: &Strings, screen: Vec2, speaker_rect: Rect) {
+    if let Some(line) = runner.visible_line() {
+        let key = translation_key(&runner.script, line);
+        let anchor = match line.speaker {
+            Speaker::Button => create_tooltip_anchor(screen.x, screen.y, speaker_rect),
+            Speaker::Center => Anchor::center_v(screen * 0.5),
+        };
+        render_tooltip(strings.get_or(&key, &line.text), anchor);
+    }
+}
+
+/// Lets writers edit the file next to the executable without recompiling
+pub async fn load_dialogs() -> Dialogs {
+    match macroquad::file::load_string(DIALOGS_PATH).await {
+        Ok(text) => match Dialogs::parse(&text) {
+            Ok(dialogs) => return dialogs,
+            Err(e) => println!("could not parse {}: {}", DIALOGS_PATH, e),
+        },
+        Err(e) => println!(
+            "could not load {}, using the embedded dialogs: {}",
+            DIALOGS_PATH, e
+        ),
+    }
+    Dialogs::embedded()
+}
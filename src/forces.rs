@@ -0,0 +1,297 @@
+//! What moves the button: fields that give it a velocity from where it is and where the mouse is.
+//! Each stage adds up its own fields, loaded from a text file so that they can be tuned without
+//! touching the code. See `assets/forces.txt` for the format.
+
+use crate::synth::Noise;
+use macroquad::math::{vec2, Rect, Vec2};
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+
+pub const FORCES_PATH: &str = "assets/forces.txt";
+pub const DEFAULT_FORCES: &str = include_str!("../assets/forces.txt");
+
+/// Where a field is sampled
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Probe {
+    /// The center of the button
+    pub pos: Vec2,
+    pub mouse: Vec2,
+    /// Where the button likes to rest
+    pub home: Vec2,
+    pub bounds: Rect,
+    /// Seconds since the stage started
+    pub time: f32,
+}
+
+pub trait ForceField {
+    /// In pixels per second
+    fn velocity(&self, probe: &Probe) -> Vec2;
+}
+
+/// Pushes away from the mouse
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Repulsion {
+    /// In pixels
+    pub range: f32,
+    pub strength: f32,
+    /// The power of how far inside the range the mouse is
+    pub falloff: f32,
+}
+
+impl Repulsion {
+    fn magnitude(&self, distance: f32) -> f32 {
+        self.strength * (self.range - distance).max(0.0).powf(self.falloff)
+    }
+}
+
+impl ForceField for Repulsion {
+    fn velocity(&self, probe: &Probe) -> Vec2 {
+        let away = probe.pos - probe.mouse;
+        away.normalize_or_zero() * self.magnitude(away.length())
+    }
+}
+
+/// Pushes around the mouse, like `Repulsion` turned a quarter clockwise
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Swirl(pub Repulsion);
+
+impl ForceField for Swirl {
+    fn velocity(&self, probe: &Probe) -> Vec2 {
+        let away = self.0.velocity(probe);
+        vec2(away.y, -away.x)
+    }
+}
+
+/// Pulls toward the home, faster the further it is
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HomeAttraction {
+    /// Per second
+    pub strength: f32,
+    /// In pixels
+    pub dead_zone: f32,
+}
+
+impl ForceField for HomeAttraction {
+    fn velocity(&self, probe: &Probe) -> Vec2 {
+        let to_home = probe.home - probe.pos;
+        if to_home.length() > self.dead_zone {
+            to_home * self.strength
+        } else {
+            Vec2::ZERO
+        }
+    }
+}
+
+/// Pushes away from the edges of the bounds
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WallRepulsion {
+    /// In pixels
+    pub margin: f32,
+    pub strength: f32,
+}
+
+impl ForceField for WallRepulsion {
+    fn velocity(&self, probe: &Probe) -> Vec2 {
+        let push = |distance: f32| self.strength * (self.margin - distance).max(0.0).powi(2);
+        let (pos, bounds) = (probe.pos, probe.bounds);
+        vec2(
+            push(pos.x - bounds.left()) - push(bounds.right() - pos.x),
+            push(pos.y - bounds.top()) - push(bounds.bottom() - pos.y),
+        )
+    }
+}
+
+/// Wanders smoothly around, the same way every time for the same seed
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NoiseField {
+    /// In pixels per second
+    pub strength: f32,
+    /// Turns per second
+    pub frequency: f32,
+    phases: [f32; 4],
+}
+
+impl NoiseField {
+    pub fn new(strength: f32, frequency: f32, seed: u32) -> Self {
+        let mut noise = Noise::new(seed);
+        let mut phase = || noise.sample() * PI;
+        Self {
+            strength,
+            frequency,
+            phases: [phase(), phase(), phase(), phase()],
+        }
+    }
+}
+
+impl ForceField for NoiseField {
+    fn velocity(&self, probe: &Probe) -> Vec2 {
+        let t = TAU * self.frequency * probe.time;
+        let wave = |a: f32, b: f32| ((t + a).sin() + (t * 2.3 + b).sin()) * 0.5;
+        let [a, b, c, d] = self.phases;
+        vec2(wave(a, b), wave(c, d)) * self.strength
+    }
+}
+
+/// Scales a field by where the mouse is compared to the way home: fully across it when the mouse
+/// is to the side, fully along it when the mouse is in the way or behind
+pub struct FacingHome<F> {
+    pub field: F,
+    pub along: bool,
+}
+
+impl<F: ForceField> ForceField for FacingHome<F> {
+    fn velocity(&self, probe: &Probe) -> Vec2 {
+        let to_home = (probe.home - probe.pos).normalize_or_zero();
+        let to_mouse = (probe.mouse - probe.pos).normalize_or_zero();
+        let along = to_home.dot(to_mouse).abs();
+        let weight = if self.along { along } else { 1.0 - along };
+        self.field.velocity(probe) * weight
+    }
+}
+
+/// The sum of its fields
+#[derive(Default)]
+pub struct Fields(pub Vec<Box<dyn ForceField>>);
+
+impl ForceField for Fields {
+    fn velocity(&self, probe: &Probe) -> Vec2 {
+        self.0
+            .iter()
+            .fold(Vec2::ZERO, |sum, field| sum + field.velocity(probe))
+    }
+}
+
+impl ForceField for Box<dyn ForceField> {
+    fn velocity(&self, probe: &Probe) -> Vec2 {
+        self.as_ref().velocity(probe)
+    }
+}
+
+/// The fields of each stage
+#[derive(Default)]
+pub struct ForceFields {
+    stages: HashMap<String, Fields>,
+}
+
+impl ForceFields {
+    pub fn embedded() -> Self {
+        Self::parse(DEFAULT_FORCES).expect("the embedded forces should be valid")
+    }
+
+    pub fn get(&self, stage: &str) -> Option<&Fields> {
+        self.stages.get(stage)
+    }
+
+    /// Stages without fields don't move the button
+    pub fn velocity(&self, stage: &str, probe: &Probe) -> Vec2 {
+        self.get(stage)
+            .map_or(Vec2::ZERO, |fields| fields.velocity(probe))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut stages = HashMap::new();
+        let mut section: Option<(String, Fields)> = None;
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: String| format!("line {}: {}", i + 1, message);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if let Some((name, fields)) = section.take() {
+                    stages.insert(name, fields);
+                }
+                section = Some((name.to_string(), Fields::default()));
+                continue;
+            }
+            let (_, fields) = section
+                .as_mut()
+                .ok_or_else(|| error("expected a [section] first".to_string()))?;
+            fields.0.push(parse_field(line).map_err(error)?);
+        }
+        if let Some((name, fields)) = section.take() {
+            stages.insert(name, fields);
+        }
+        Ok(Self { stages })
+    }
+}
+
+/// `[across|along] <field> <numbers...>`
+fn parse_field(line: &str) -> Result<Box<dyn ForceField>, String> {
+    let mut words = line.split_whitespace().collect::<Vec<_>>();
+    let facing = match words.first() {
+        Some(&"across") => Some(false),
+        Some(&"along") => Some(true),
+        _ => None,
+    };
+    if facing.is_some() {
+        words.remove(0);
+    }
+    let (name, numbers) = words
+        .split_first()
+        .ok_or_else(|| "expected a field".to_string())?;
+    let numbers = numbers
+        .iter()
+        .map(|word| word.parse::<f32>().map_err(|e| format!("{}: {}", word, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let expected = |count: usize| {
+        if numbers.len() == count {
+            Ok(())
+        } else {
+            Err(format!("{} expects {} numbers", name, count))
+        }
+    };
+    let repulsion = || Repulsion {
+        range: numbers[0],
+        strength: numbers[1],
+        falloff: numbers[2],
+    };
+    let field: Box<dyn ForceField> = match *name {
+        "repulsion" => {
+            expected(3)?;
+            Box::new(repulsion())
+        }
+        "swirl" => {
+            expected(3)?;
+            Box::new(Swirl(repulsion()))
+        }
+        "home" => {
+            expected(2)?;
+            Box::new(HomeAttraction {
+                strength: numbers[0],
+                dead_zone: numbers[1],
+            })
+        }
+        "walls" => {
+            expected(2)?;
+            Box::new(WallRepulsion {
+                margin: numbers[0],
+                strength: numbers[1],
+            })
+        }
+        "noise" => {
+            expected(3)?;
+            Box::new(NoiseField::new(numbers[0], numbers[1], numbers[2] as u32))
+        }
+        _ => return Err(format!("unknown field {}", name)),
+    };
+    Ok(match facing {
+        Some(along) => Box::new(FacingHome { field, along }),
+        None => field,
+    })
+}
+
+/// Lets designers edit the file next to the executable without recompiling
+pub async fn load_forces() -> ForceFields {
+    match macroquad::file::load_string(FORCES_PATH).await {
+        Ok(text) => match ForceFields::parse(&text) {
+            Ok(forces) => return forces,
+            Err(e) => println!("could not parse {}: {}", FORCES_PATH, e),
+        },
+        Err(e) => println!(
+            "could not load {}, using the embedded forces: {}",
+            FORCES_PATH, e
+        ),
+    }
+    ForceFields::embedded()
+}
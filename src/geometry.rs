@@ -0,0 +1,119 @@
+//! Convex shapes and the separating axis test, including a swept version that catches shapes
+//! passing through each other between two frames.
+
+use macroquad::color::Color;
+use macroquad::math::{Rect, Vec2};
+use macroquad::prelude::{draw_circle_lines, draw_line};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    /// Convex, with the points in order, clockwise or not
+    Polygon(Vec<Vec2>),
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+}
+
+impl Shape {
+    pub fn triangle(a: Vec2, b: Vec2, c: Vec2) -> Self {
+        Shape::Polygon(vec![a, b, c])
+    }
+
+    pub fn rect(rect: Rect) -> Self {
+        Shape::Polygon(vec![
+            rect.point(),
+            Vec2::new(rect.right(), rect.top()),
+            Vec2::new(rect.right(), rect.bottom()),
+            Vec2::new(rect.left(), rect.bottom()),
+        ])
+    }
+
+    pub fn circle(center: Vec2, radius: f32) -> Self {
+        Shape::Circle { center, radius }
+    }
+
+    pub fn translated(&self, offset: Vec2) -> Self {
+        match self {
+            Shape::Polygon(points) => Shape::Polygon(points.iter().map(|p| *p + offset).collect()),
+            Shape::Circle { center, radius } => Shape::circle(*center + offset, *radius),
+        }
+    }
+
+    /// The interval that the shape covers along `axis`, which should be normalized
+    pub fn project(&self, axis: Vec2) -> (f32, f32) {
+        match self {
+            Shape::Polygon(points) => points.iter().fold((f32::MAX, f32::MIN), |(min, max), p| {
+                let d = p.dot(axis);
+                (min.min(d), max.max(d))
+            }),
+            Shape::Circle { center, radius } => {
+                let d = center.dot(axis);
+                (d - radius, d + radius)
+            }
+        }
+    }
+
+    /// The axes where this shape could be separated from `other`
+    fn axes(&self, other: &Shape) -> Vec<Vec2> {
+        match self {
+            Shape::Polygon(points) => (0..points.len())
+                .map(|i| {
+                    let edge = points[(i + 1) % points.len()] - points[i];
+                    edge.perp().normalize_or_zero()
+                })
+                .collect(),
+            Shape::Circle { center, .. } => {
+                let closest = match other {
+                    Shape::Polygon(points) => points
+                        .iter()
+                        .copied()
+                        .min_by(|a, b| a.distance(*center).total_cmp(&b.distance(*center))),
+                    Shape::Circle { center, .. } => Some(*center),
+                };
+                closest
+                    .map(|closest| (closest - *center).normalize_or_zero())
+                    .into_iter()
+                    .collect()
+            }
+        }
+    }
+}
+
+pub fn overlaps(a: &Shape, b: &Shape) -> bool {
+    swept_overlaps(a, Vec2::ZERO, b)
+}
+
+/// Whether `a` touches `b` at any moment while moving by `motion`. For two moving shapes, use
+/// the motion of `a` minus the motion of `b`.
+pub fn swept_overlaps(a: &Shape, motion: Vec2, b: &Shape) -> bool {
+    let end = a.translated(motion);
+    let mut axes = a.axes(b);
+    axes.extend(b.axes(a));
+    if motion != Vec2::ZERO {
+        axes.extend(end.axes(b));
+        axes.extend(b.axes(&end));
+        axes.push(motion.perp().normalize());
+    }
+    axes.into_iter().all(|axis| {
+        let (min_a, max_a) = a.project(axis);
+        let along = motion.dot(axis);
+        let (min_a, max_a) = (min_a + along.min(0.0), max_a + along.max(0.0));
+        let (min_b, max_b) = b.project(axis);
+        max_a >= min_b && max_b >= min_a
+    })
+}
+
+pub fn draw_shape_lines(shape: &Shape, thickness: f32, color: Color) {
+    match shape {
+        Shape::Polygon(points) => {
+            for (i, from) in points.iter().enumerate() {
+                let to = points[(i + 1) % points.len()];
+                draw_line(from.x, from.y, to.x, to.y, thickness, color);
+            }
+        }
+        Shape::Circle { center, radius } => {
+            draw_circle_lines(center.x, center.y, *radius, thickness, color)
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! Where rockets come from and when they leave: spawn points, cooldowns, and a wind-up that
+//! telegraphs every launch before the rocket appears. The rules are loaded from a text file, see
+//! `assets/launch.txt` for the format.
+
+use crate::steering::RocketKind;
+use macroquad::math::{vec2, Rect, Vec2};
+
+pub const LAUNCH_PATH: &str = "assets/launch.txt";
+pub const DEFAULT_LAUNCH: &str = include_str!("../assets/launch.txt");
+
+/// Radians away from straight up, taken in turns by the rockets leaving the button
+const BUTTON_ANGLES: [f32; 5] = [0.0, 0.5, -0.5, 1.0, -1.0];
+/// Fractions of the screen, taken in turns by the rockets coming from the edges
+const EDGE_SPOTS: [(f32, f32); 6] = [
+    (0.5, 0.0),
+    (1.0, 0.5),
+    (0.5, 1.0),
+    (0.0, 0.5),
+    (0.0, 0.0),
+    (1.0, 1.0),
+];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpawnPoint {
+    /// Out of the button, fanning upwards
+    Button,
+    /// From the edges of the screen, toward the center
+    ScreenEdge,
+    /// A fixed turret, as a fraction of the screen, aiming at the button
+    Turret(Vec2),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpawnRule {
+    pub point: SpawnPoint,
+    /// The first wave that uses it
+    pub from_wave: usize,
+}
+
+/// What a designer can tune for a stage
+#[derive(Clone, Debug, PartialEq)]
+pub struct LaunchRules {
+    /// Taken in turns, among the ones unlocked by the current wave
+    pub spawns: Vec<SpawnRule>,
+    /// Minimum seconds between two launches
+    pub cooldown: f32,
+    /// Seconds of wind-up before a rocket appears
+    pub telegraph: f32,
+    /// Launches wait while this many rockets fly
+    pub max_flying: usize,
+}
+
+impl LaunchRules {
+    pub fn embedded() -> Self {
+        Self::parse(DEFAULT_LAUNCH).expect("the embedded launch rules should be valid")
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut spawns = Vec::new();
+        let (mut cooldown, mut telegraph, mut max_flying) = (None, None, None);
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: String| format!("line {}: {}", i + 1, message);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            let number = |word: &str| {
+                word.parse::<f32>()
+                    .map_err(|e| error(format!("{}: {}", word, e)))
+            };
+            match words.as_slice() {
+                ["cooldown", seconds] => cooldown = Some(number(seconds)?),
+                ["telegraph", seconds] => telegraph = Some(number(seconds)?),
+                ["max_flying", count] => {
+                    let count = count
+                        .parse::<usize>()
+                        .map_err(|e| error(format!("{}: {}", count, e)))?;
+                    max_flying = Some(count);
+                }
+                ["spawn", wave, point @ ..] => {
+                    let from_wave = wave
+                        .parse::<usize>()
+                        .map_err(|e| error(format!("{}: {}", wave, e)))?;
+                    let point = match point {
+                        ["button"] => SpawnPoint::Button,
+                        ["edge"] => SpawnPoint::ScreenEdge,
+                        ["turret", x, y] => SpawnPoint::Turret(vec2(number(x)?, number(y)?)),
+                        _ => return Err(error(format!("unknown spawn point {}", point.join(" ")))),
+                    };
+                    spawns.push(SpawnRule { point, from_wave });
+                }
+                _ => return Err(error(format!("unknown rule {}", line))),
+            }
+        }
+        let missing = |key: &str| format!("missing {}", key);
+        Ok(Self {
+            spawns,
+            cooldown: cooldown.ok_or_else(|| missing("cooldown"))?,
+            telegraph: telegraph.ok_or_else(|| missing("telegraph"))?,
+            max_flying: max_flying.ok_or_else(|| missing("max_flying"))?,
+        })
+    }
+
+    /// The turrets unlocked by `wave`
+    pub fn turrets(&self, wave: usize) -> impl Iterator<Item = Vec2> + '_ {
+        self.spawns
+            .iter()
+            .filter(move |rule| rule.from_wave <= wave)
+            .filter_map(|rule| match rule.point {
+                SpawnPoint::Turret(at) => Some(at),
+                _ => None,
+            })
+    }
+}
+
+/// A launch that is winding up
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Telegraph {
+    pub kind: RocketKind,
+    pub point: SpawnPoint,
+    /// How many rockets left from this kind of spawn point before, to take turns
+    pub turn: usize,
+    /// Seconds since the wind-up started
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+impl Telegraph {
+    /// From 0 to 1
+    pub fn progress(&self) -> f32 {
+        if self.duration > 0.0 {
+            (self.elapsed / self.duration).min(1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+pub struct Launcher {
+    rules: LaunchRules,
+    /// Requested by the waves, with their wave number, not winding up yet
+    queue: Vec<(RocketKind, usize)>,
+    winding_up: Option<Telegraph>,
+    cooldown_left: f32,
+    /// Launches so far, per index of `rules.spawns`
+    launched: Vec<usize>,
+    /// Launches so far
+    total: usize,
+}
+
+impl Launcher {
+    pub fn new(rules: LaunchRules) -> Self {
+        let launched = vec![0; rules.spawns.len()];
+        Self {
+            rules,
+            queue: Vec::new(),
+            winding_up: None,
+            cooldown_left: 0.0,
+            launched,
+            total: 0,
+        }
+    }
+
+    pub fn rules(&self) -> &LaunchRules {
+        &self.rules
+    }
+
+    pub fn request(&mut self, kind: RocketKind, wave: usize) {
+        self.queue.push((kind, wave));
+    }
+
+    /// Rockets requested that didn't launch yet
+    pub fn pending(&self) -> usize {
+        self.queue.len() + self.winding_up.is_some() as usize
+    }
+
+    pub fn telegraph(&self) -> Option<&Telegraph> {
+        self.winding_up.as_ref()
+    }
+
+    /// `flying` is how many rockets are alive. Returns the launch that finished winding up.
+    pub fn step(&mut self, dt: f32, flying: usize) -> Option<Telegraph> {
+        self.cooldown_left -= dt;
+        if let Some(telegraph) = self.winding_up.as_mut() {
+            telegraph.elapsed += dt;
+            if telegraph.elapsed < telegraph.duration {
+                return None;
+            }
+            self.cooldown_left = self.rules.cooldown;
+            return self.winding_up.take();
+        }
+        if self.queue.is_empty() || self.cooldown_left > 0.0 || flying >= self.rules.max_flying {
+            return None;
+        }
+        let (kind, wave) = self.queue.remove(0);
+        let unlocked: Vec<usize> = (0..self.rules.spawns.len())
+            .filter(|i| self.rules.spawns[*i].from_wave <= wave)
+            .collect();
+        let rule = *unlocked.get(self.total % unlocked.len().max(1))?;
+        self.winding_up = Some(Telegraph {
+            kind,
+            point: self.rules.spawns[rule].point,
+            turn: self.launched[rule],
+            elapsed: 0.0,
+            duration: self.rules.telegraph,
+        });
+        self.launched[rule] += 1;
+        self.total += 1;
+        self.step(0.0, flying)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Launcher::new(self.rules.clone());
+    }
+}
+
+/// Where a rocket leaving from `point` appears, and its direction. `clearance` is how far from
+/// the button or the screen edge it starts, so it doesn't touch them.
+pub fn place(
+    point: SpawnPoint,
+    turn: usize,
+    button: Rect,
+    screen: Vec2,
+    clearance: f32,
+) -> (Vec2, Vec2) {
+    match point {
+        SpawnPoint::Button => {
+            let angle = BUTTON_ANGLES[turn % BUTTON_ANGLES.len()];
+            let dir = vec2(0.0, -1.0).rotate(Vec2::from_angle(angle));
+            let half = button.size() * 0.5;
+            let to_edge = (half.x / dir.x.abs()).min(half.y / dir.y.abs());
+            (button.center() + dir * (to_edge + clearance), dir)
+        }
+        SpawnPoint::ScreenEdge => {
+            let (x, y) = EDGE_SPOTS[turn % EDGE_SPOTS.len()];
+            let edge = vec2(x, y) * screen;
+            let dir = (screen * 0.5 - edge)
+                .try_normalize()
+                .unwrap_or(vec2(0.0, 1.0));
+            (edge + dir * clearance, dir)
+        }
+        SpawnPoint::Turret(at) => {
+            let pos = at * screen;
+            let dir = (button.center() - pos)
+                .try_normalize()
+                .unwrap_or(vec2(0.0, -1.0));
+            (pos + dir * clearance, dir)
+        }
+    }
+}
+
+/// Lets designers edit the file next to the executable without recompiling
+pub async fn load_launch_rules() -> LaunchRules {
+    match macroquad::file::load_string(LAUNCH_PATH).await {
+        Ok(text) => match LaunchRules::parse(&text) {
+            Ok(rules) => return rules,
+            Err(e) => println!("could not parse {}: {}", LAUNCH_PATH, e),
+        },
+        Err(e) => println!(
+            "could not load {}, using the embedded launch rules: {}",
+            LAUNCH_PATH, e
+        ),
+    }
+    LaunchRules::embedded()
+}
@@ -1,5 +1,8 @@
+// juquad widgets take the input as `&Box<dyn InputTrait>`, so the stages pass it along the same way
+#![allow(clippy::borrowed_box)]
+
+use crate::audio::{Mixer, SoundEffect};
 use juquad::draw::{draw_rect, draw_rect_lines};
-use juquad::input::input_macroquad::InputMacroquad;
 use juquad::input::input_trait::InputTrait;
 use juquad::widgets::anchor::{Anchor, Horizontal, Vertical};
 use juquad::widgets::button::Button;
@@ -7,31 +10,53 @@ use juquad::widgets::text::TextRect;
 use juquad::widgets::{StateStyle, Style, Widget};
 use juquad::PixelPosition;
 use macroquad::color::{Color, LIGHTGRAY};
-use macroquad::input::{is_key_pressed, KeyCode, MouseButton};
+use macroquad::input::{KeyCode, MouseButton};
 use macroquad::math::{Rect, Vec2};
-use macroquad::prelude::{draw_poly, vec2, DARKGREEN};
+use macroquad::prelude::{draw_poly, DARKGREEN};
+use macroquad::text::{Font, TextDimensions};
 
+pub mod menus {
+    pub mod bindings;
+    pub mod column;
+    pub mod pause;
+    pub mod settings;
+}
 pub mod stages {
-    pub mod game_over;
-    pub mod game_won;
+    pub mod driver;
+    pub mod end_screen;
     pub mod prison;
     pub mod rockets;
     pub mod torus;
 }
+pub mod achievements;
+pub mod actions;
+pub mod audio;
+pub mod bot;
+pub mod dev_overlay;
+pub mod dialog;
+pub mod forces;
+pub mod geometry;
+pub mod key_names;
+pub mod launcher;
+pub mod particles;
+pub mod replay;
+pub mod save;
+pub mod settings;
+pub mod steering;
+pub mod strings;
+pub mod synth;
+pub mod timestep;
+pub mod torus_space;
+pub mod virtual_cursor;
+pub mod waves;
 
 pub const FONT_SIZE: f32 = 16.0;
 
 pub const STAGE_TORUS_ENABLED: bool = true;
 
-const DIALOG_DELAY_SECONDS: f64 = 5.0;
-pub const STAGE_TORUS_DIALOGS: &[&str] =
-    &["Hey, don't scare me like that!", "You wanna play, huh?"];
-pub const STAGE_PRISON_DIALOGS: &[&str] = &[
-    "You can no longer leave this window!",
-    "You can give up if you want...",
-];
-
 pub const FORCE_RANGE_PIXELS: f32 = 100.0;
+/// In pixels per second. The button moves this fast when the mouse is at half the force range.
+const WHOOSH_SPEED: f32 = 1500.0;
 pub const LIGHT_GREEN: Color = Color::new(0.7, 0.85, 0.7, 1.0);
 const LIGHTER_GREEN: Color = Color::new(0.8, 0.9, 0.8, 1.0);
 pub const TOOLTIP_BACKGROUND: Color = LIGHTER_GREEN;
@@ -53,10 +78,6 @@ pub const STYLE: Style = Style {
     },
 };
 
-fn should_quit() -> bool {
-    is_key_pressed(KeyCode::Escape)
-}
-
 fn with_alpha(base: Color, alpha: f32) -> Color {
     Color::new(base.r, base.g, base.b, alpha)
 }
@@ -64,15 +85,16 @@ pub fn draw_halo(x: f32, y: f32, r: f32, color: Color) {
     draw_poly(x, y, 40, r, 0., color);
 }
 
+/// Input for widgets that should react to a `VirtualCursor` instead of the OS pointer
 pub struct GrabbedMouseInput {
     inner: Box<dyn InputTrait>,
-    mouse_origin: Vec2,
+    virtual_pos: Vec2,
 }
 impl GrabbedMouseInput {
-    pub fn new(mouse_origin: Vec2) -> Self {
+    pub fn new(inner: &Box<dyn InputTrait>, virtual_pos: Vec2) -> Self {
         Self {
-            inner: Box::new(InputMacroquad),
-            mouse_origin,
+            inner: (*inner).clone(),
+            virtual_pos,
         }
     }
 }
@@ -98,7 +120,7 @@ impl InputTrait for GrabbedMouseInput {
     }
 
     fn mouse_position(&self) -> PixelPosition {
-        self.inner.mouse_position() + self.mouse_origin
+        self.virtual_pos
     }
 
     fn mouse_wheel(&self) -> PixelPosition {
@@ -108,10 +130,11 @@ impl InputTrait for GrabbedMouseInput {
     fn clone(&self) -> Box<dyn InputTrait> {
         Box::new(GrabbedMouseInput {
             inner: self.inner.clone(),
-            mouse_origin: self.mouse_origin,
+            virtual_pos: self.virtual_pos,
         })
     }
 }
+#[allow(unused)]
 fn animate_pos_to(button: &mut Button, target_pos: Vec2) {
     button.reanchor(Anchor::center_v(target_pos))
 }
@@ -126,45 +149,15 @@ fn create_tooltip_anchor(sw: f32, sh: f32, next_to_this: Rect) -> Anchor {
     };
     let top = center.y < sh * 0.5;
     let vert = if top { Vertical::Top } else { Vertical::Bottom };
-    let anchor = Anchor::new(horiz, vert, center.x, center.y);
-    anchor
-}
-
-fn compute_force(mouse_pos: Vec2, button_center: Vec2) -> Vec2 {
-    let range = FORCE_RANGE_PIXELS;
-    let diff = button_center - mouse_pos;
-    let diff_unit = diff.normalize_or_zero();
-    let magnitude = diff.length();
-    let force = (range - magnitude).max(0.0);
-    // let range = 100.0;
-    // let complementary = vec2(range, range) - diff.abs();
-    // let clamped = vec2(complementary.x.max(0.0), complementary.y.max(0.0));
-    // let force = clamped.length_squared();
-    let displacement = diff_unit * force * force * 0.01;
-    displacement
-}
-fn compute_force_towards(mouse_pos: Vec2, button_center: Vec2, target: Vec2) -> Vec2 {
-    let range = FORCE_RANGE_PIXELS;
-    let diff = button_center - mouse_pos;
-    let diff_unit = diff.normalize_or_zero();
-    let left_diff = vec2(diff_unit.y, -diff_unit.x);
-    let to_target = target - button_center;
-    let to_target_unit = (to_target).normalize_or_zero();
-    let magnitude = diff.length();
-    let force = (range - magnitude).max(0.0);
-    let displacement = diff_unit * force * force * 0.01;
-    let cos = to_target_unit.dot((mouse_pos - button_center).normalize_or_zero());
-    let sideways = 1.0 - cos.abs();
-    let sideways_displacement = left_diff * force * force * 0.01;
-    let center_fixed = if to_target.length_squared() > 10.0 {
-        to_target * 0.01
-    } else {
-        vec2(0.0, 0.0)
-    };
-
-    displacement * sideways + (1.0 - sideways) * sideways_displacement + center_fixed
+    Anchor::new(horiz, vert, center.x, center.y)
 }
 
+/// Plays the whoosh when the button is pushed fast enough to be heard
+fn whoosh(audio: &mut Mixer, displacement: Vec2, dt: f32) {
+    if displacement.length() > WHOOSH_SPEED * dt {
+        audio.play(SoundEffect::Whoosh);
+    }
+}
 #[allow(unused)]
 fn move_inside(rect: &mut Rect, container: Rect) {
     rect.x += (container.x - rect.x).max(0.0);
@@ -176,23 +169,38 @@ fn move_inside(rect: &mut Rect, container: Rect) {
 pub fn new_button(text: &str, anchor: Anchor) -> Button {
     Button::new(text, anchor, FONT_SIZE)
 }
-pub fn new_button_grabbed(text: &str, anchor: Anchor, input: &Box<dyn InputTrait>) -> Button {
+pub type MeasureText = fn(&str, Option<Font>, u16, f32) -> TextDimensions;
+
+pub fn new_button_generic(
+    text: &str,
+    anchor: Anchor,
+    input: &Box<dyn InputTrait>,
+    measure_text: MeasureText,
+) -> Button {
     let input_clone = (*input).clone();
-    Button::new_generic(
-        text,
-        anchor,
-        FONT_SIZE,
-        None,
-        macroquad::prelude::measure_text,
-        input_clone,
-    )
+    Button::new_generic(text, anchor, FONT_SIZE, None, measure_text, input_clone)
+}
+
+/// Rough text size for when there's no window to load fonts, like in tests
+pub fn measure_text_headless(
+    text: &str,
+    _font: Option<Font>,
+    font_size: u16,
+    font_scale: f32,
+) -> TextDimensions {
+    let size = font_size as f32 * font_scale;
+    TextDimensions {
+        width: text.chars().count() as f32 * size * 0.5,
+        height: size,
+        offset_y: size * 0.75,
+    }
 }
 pub fn render_button(button: &Button) {
     button.render_default(&STYLE);
 }
 
 pub fn render_tooltip(text: &str, anchor: Anchor) {
-    let text_rect = TextRect::new(&text, anchor, FONT_SIZE);
+    let text_rect = TextRect::new(text, anchor, FONT_SIZE);
     draw_rect(text_rect.rect(), TOOLTIP_BACKGROUND);
     draw_rect_lines(text_rect.rect(), 2.0, STYLE.at_rest.border_color);
     text_rect.render_default(&STYLE.at_rest);
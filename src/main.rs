@@ -1,6 +1,6 @@
 use macroquad::prelude::*;
-use press_to_start::stages::rockets::stage_rockets;
-use press_to_start::stages::torus::stage_torus;
+use press_to_start::replay::Recording;
+use press_to_start::stages::driver::{run_stages, InputMode, StageGraph};
 
 const DEFAULT_WINDOW_WIDTH: i32 = 800;
 const DEFAULT_WINDOW_HEIGHT: i32 = 600;
@@ -18,6 +18,21 @@ fn window_conf() -> Conf {
 
 #[macroquad::main(window_conf)]
 async fn main() {
-    let button = stage_torus().await;
-    stage_rockets(button).await;
+    run_stages(StageGraph::campaign(), parse_input_mode()).await;
+}
+
+/// `--record <path>` saves the input of the session, `--replay <path>` plays it back
+fn parse_input_mode() -> InputMode {
+    let args = std::env::args().collect::<Vec<_>>();
+    match (args.get(1).map(|s| s.as_str()), args.get(2)) {
+        (Some("--record"), Some(path)) => InputMode::Record(path.clone()),
+        (Some("--replay"), Some(path)) => match Recording::load(path) {
+            Ok(recording) => InputMode::Replay(recording),
+            Err(e) => {
+                println!("could not load replay: {}", e);
+                InputMode::Live
+            }
+        },
+        _ => InputMode::Live,
+    }
 }
@@ -0,0 +1,95 @@
+use crate::actions::{pressed_binding, Action, Bindings, ALL_ACTIONS};
+use crate::menus::column::{menu_top, render_menu_backdrop, ButtonColumn};
+use crate::render_tooltip;
+use crate::stages::driver::{Menu, MenuOutcome, Shared};
+use crate::FONT_SIZE;
+use juquad::input::input_trait::InputTrait;
+use juquad::widgets::anchor::{Anchor, Horizontal};
+
+/// Click an action, then press the key or mouse button that should trigger it
+#[derive(Default)]
+pub struct BindingsMenu {
+    waiting: Option<Action>,
+    /// Why the last rebinding was rejected
+    conflict: Option<(Action, Action)>,
+    column: Option<ButtonColumn>,
+}
+
+impl BindingsMenu {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    fn row_text(&self, shared: &Shared, action: Action) -> String {
+        let bindings = if self.waiting == Some(action) {
+            shared.text("press_a_key").to_string()
+        } else {
+            let names = shared.bindings.get(action).iter().map(|b| b.name());
+            names.collect::<Vec<_>>().join(", ")
+        };
+        format!("{}: {}", shared.text(&action.name_key()), bindings)
+    }
+}
+
+impl Menu for BindingsMenu {
+    fn step(
+        &mut self,
+        shared: &mut Shared,
+        input: &Box<dyn InputTrait>,
+        _dt: f32,
+    ) -> Option<MenuOutcome> {
+        if let Some(action) = self.waiting {
+            if let Some(binding) = pressed_binding(input) {
+                self.waiting = None;
+                match shared.bindings.rebind(action, binding) {
+                    Ok(()) => {
+                        self.conflict = None;
+                        shared.save_bindings();
+                    }
+                    Err(other) => self.conflict = Some((action, other)),
+                }
+            }
+        } else if shared.is_pressed(Action::Quit, input) {
+            return Some(MenuOutcome::Close);
+        }
+
+        let mut column = ButtonColumn::new(menu_top(shared.screen));
+        for action in ALL_ACTIONS {
+            if column.add(shared, &self.row_text(shared, action), input) {
+                self.waiting = if self.waiting == Some(action) {
+                    None
+                } else {
+                    Some(action)
+                };
+            }
+        }
+        column.separate();
+        if column.add(shared, shared.text("reset_defaults"), input) {
+            shared.bindings = Bindings::default();
+            shared.save_bindings();
+            self.waiting = None;
+            self.conflict = None;
+        }
+        if column.add(shared, shared.text("back"), input) {
+            return Some(MenuOutcome::Close);
+        }
+        self.column = Some(column);
+        None
+    }
+
+    fn render(&self, shared: &Shared) {
+        render_menu_backdrop(shared, shared.text("key_bindings_title"));
+        if let Some(column) = &self.column {
+            column.render();
+            if let (Some((action, other)), Some(rect)) = (self.conflict, column.rect()) {
+                let text = format!(
+                    "{}: {} {}",
+                    shared.text(&action.name_key()),
+                    shared.text("binding_conflict"),
+                    shared.text(&other.name_key()),
+                );
+                let anchor = Anchor::below(rect, Horizontal::Center, FONT_SIZE);
+                render_tooltip(&text, anchor);
+            }
+        }
+    }
+}
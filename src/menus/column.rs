@@ -0,0 +1,69 @@
+use crate::stages::driver::Shared;
+use crate::{render_button, with_alpha, FONT_SIZE, STYLE};
+use juquad::draw::draw_rect;
+use juquad::input::input_trait::InputTrait;
+use juquad::widgets::anchor::{Anchor, Horizontal};
+use juquad::widgets::button::Button;
+use juquad::widgets::text::TextRect;
+use juquad::widgets::Widget;
+use macroquad::color::LIGHTGRAY;
+use macroquad::math::{vec2, Rect, Vec2};
+
+/// Buttons stacked from top to bottom, rebuilt every step so that they follow the text
+pub struct ButtonColumn {
+    next: Anchor,
+    gap: f32,
+    buttons: Vec<Button>,
+}
+
+impl ButtonColumn {
+    pub fn new(top_center: Vec2) -> Self {
+        Self {
+            next: Anchor::top_center(top_center.x, top_center.y),
+            gap: 0.0,
+            buttons: Vec::new(),
+        }
+    }
+
+    /// Returns true if the new button was clicked
+    pub fn add(&mut self, shared: &Shared, text: &str, input: &Box<dyn InputTrait>) -> bool {
+        if let Some(last) = self.buttons.last() {
+            self.next = Anchor::below(last.rect(), Horizontal::Center, self.gap);
+        }
+        self.gap = 0.0;
+        let mut button = shared.new_button(text, self.next, input);
+        let clicked = button.interact().is_clicked();
+        self.buttons.push(button);
+        clicked
+    }
+
+    /// Leaves some room before the next button
+    pub fn separate(&mut self) {
+        self.gap = FONT_SIZE;
+    }
+
+    pub fn rect(&self) -> Option<Rect> {
+        let first = self.buttons.first()?.rect();
+        Some(first.combine_with(self.buttons.last()?.rect()))
+    }
+
+    pub fn render(&self) {
+        for button in &self.buttons {
+            render_button(button);
+        }
+    }
+}
+
+/// Where the first button of every menu goes
+pub fn menu_top(screen: Vec2) -> Vec2 {
+    vec2(screen.x * 0.5, screen.y * 0.2)
+}
+
+/// Covers the stage below the menu and writes the title at the top
+pub fn render_menu_backdrop(shared: &Shared, title: &str) {
+    let screen = Rect::new(0.0, 0.0, shared.screen.x, shared.screen.y);
+    draw_rect(screen, with_alpha(LIGHTGRAY, 0.95));
+    let anchor = Anchor::center(shared.screen.x * 0.5, shared.screen.y * 0.1);
+    let title = TextRect::new(title, anchor, FONT_SIZE * 2.0);
+    title.render_default(&STYLE.at_rest);
+}
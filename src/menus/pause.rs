@@ -0,0 +1,55 @@
+use crate::actions::Action;
+use crate::menus::column::{menu_top, render_menu_backdrop, ButtonColumn};
+use crate::menus::settings::SettingsMenu;
+use crate::stages::driver::{Menu, MenuOutcome, Shared};
+use juquad::input::input_trait::InputTrait;
+
+/// Opened with the quit or pause keys from any stage
+#[derive(Default)]
+pub struct PauseMenu {
+    column: Option<ButtonColumn>,
+}
+
+impl PauseMenu {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Menu for PauseMenu {
+    fn step(
+        &mut self,
+        shared: &mut Shared,
+        input: &Box<dyn InputTrait>,
+        _dt: f32,
+    ) -> Option<MenuOutcome> {
+        if shared.is_pressed(Action::Quit, input) || shared.is_pressed(Action::Pause, input) {
+            return Some(MenuOutcome::Close);
+        }
+        let mut column = ButtonColumn::new(menu_top(shared.screen));
+        let resume = column.add(shared, shared.text("resume"), input);
+        let restart = column.add(shared, shared.text("restart_stage"), input);
+        let settings = column.add(shared, shared.text("settings_title"), input);
+        let quit = column.add(shared, shared.text("quit_to_desktop"), input);
+        self.column = Some(column);
+
+        if resume {
+            Some(MenuOutcome::Close)
+        } else if restart {
+            Some(MenuOutcome::RestartStage)
+        } else if settings {
+            Some(MenuOutcome::Open(Box::new(SettingsMenu::new())))
+        } else if quit {
+            Some(MenuOutcome::QuitToDesktop)
+        } else {
+            None
+        }
+    }
+
+    fn render(&self, shared: &Shared) {
+        render_menu_backdrop(shared, shared.text("paused"));
+        if let Some(column) = &self.column {
+            column.render();
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::actions::Action;
+use crate::menus::bindings::BindingsMenu;
+use crate::menus::column::{menu_top, render_menu_backdrop, ButtonColumn};
+use crate::settings::{next_step, VOLUME_STEPS};
+use crate::stages::driver::{Menu, MenuOutcome, Shared};
+use juquad::input::input_trait::InputTrait;
+
+/// Every option is a button that switches to the next value when clicked
+#[derive(Default)]
+pub struct SettingsMenu {
+    column: Option<ButtonColumn>,
+}
+
+impl SettingsMenu {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn percent(volume: f32) -> String {
+    format!("{}%", (volume * 100.0).round())
+}
+
+fn row(shared: &Shared, name_key: &str, value: &str) -> String {
+    format!("{}: {}", shared.text(name_key), value)
+}
+
+impl Menu for SettingsMenu {
+    fn step(
+        &mut self,
+        shared: &mut Shared,
+        input: &Box<dyn InputTrait>,
+        _dt: f32,
+    ) -> Option<MenuOutcome> {
+        if shared.is_pressed(Action::Quit, input) {
+            return Some(MenuOutcome::Close);
+        }
+        let settings = shared.settings.clone();
+        let on_off = |on: bool| shared.text(if on { "on" } else { "off" }).to_string();
+        let difficulty_key = format!("difficulty.{}", settings.difficulty.id());
+        let window_key = format!("window_mode.{}", settings.window_mode.id());
+        let volumes = settings.volumes;
+        let rows = [
+            row(shared, "setting.language", shared.text("language_name")),
+            row(shared, "setting.difficulty", shared.text(&difficulty_key)),
+            row(
+                shared,
+                "setting.reduced_motion",
+                &on_off(settings.reduced_motion),
+            ),
+            row(
+                shared,
+                "setting.prison_sensitivity",
+                &format!("{}", settings.prison_sensitivity),
+            ),
+            row(shared, "setting.window_mode", shared.text(&window_key)),
+            row(shared, "setting.master_volume", &percent(volumes.master)),
+            row(shared, "setting.effects_volume", &percent(volumes.effects)),
+            row(shared, "setting.dialog_volume", &percent(volumes.dialog)),
+        ];
+
+        let mut column = ButtonColumn::new(menu_top(shared.screen));
+        let clicked = rows
+            .iter()
+            .map(|text| column.add(shared, text, input))
+            .collect::<Vec<_>>();
+        column.separate();
+        let open_bindings = column.add(shared, shared.text("key_bindings_title"), input);
+        let back = column.add(shared, shared.text("back"), input);
+        self.column = Some(column);
+
+        if clicked[0] {
+            let next = shared.strings.next_language().to_string();
+            shared.choose_language(&next, input);
+        }
+        if clicked.iter().skip(1).any(|c| *c) {
+            let settings = &mut shared.settings;
+            if clicked[1] {
+                settings.difficulty = settings.difficulty.next();
+            }
+            if clicked[2] {
+                settings.reduced_motion = !settings.reduced_motion;
+            }
+            if clicked[3] {
+                settings.prison_sensitivity = settings.next_prison_sensitivity();
+            }
+            if clicked[4] {
+                settings.window_mode = settings.window_mode.next();
+            }
+            let volumes = &mut settings.volumes;
+            if clicked[5] {
+                volumes.master = next_step(&VOLUME_STEPS, volumes.master);
+            }
+            if clicked[6] {
+                volumes.effects = next_step(&VOLUME_STEPS, volumes.effects);
+            }
+            if clicked[7] {
+                volumes.dialog = next_step(&VOLUME_STEPS, volumes.dialog);
+            }
+            shared.save_settings();
+        }
+        if open_bindings {
+            return Some(MenuOutcome::Open(Box::new(BindingsMenu::new())));
+        }
+        if back {
+            return Some(MenuOutcome::Close);
+        }
+        None
+    }
+
+    fn render(&self, shared: &Shared) {
+        render_menu_backdrop(shared, shared.text("settings_title"));
+        if let Some(column) = &self.column {
+            column.render();
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Short-lived particles for exhaust trails, explosions and sparks.
+//!
+//! The pool never grows past its capacity, so a busy effect drops new particles instead of
+//! allocating every frame.
+
+use crate::synth::Noise;
+use macroquad::color::Color;
+use macroquad::math::Vec2;
+use macroquad::prelude::draw_circle;
+
+const EXHAUST_COLORS: &[Color] = &[
+    Color::new(1.0, 0.95, 0.6, 1.0),
+    Color::new(1.0, 0.5, 0.1, 0.8),
+    Color::new(0.4, 0.4, 0.4, 0.0),
+];
+const EXPLOSION_COLORS: &[Color] = &[
+    Color::new(1.0, 1.0, 0.9, 1.0),
+    Color::new(1.0, 0.6, 0.1, 1.0),
+    Color::new(0.8, 0.1, 0.0, 0.7),
+    Color::new(0.2, 0.2, 0.2, 0.0),
+];
+const SPARK_COLORS: &[Color] = &[
+    Color::new(1.0, 1.0, 1.0, 1.0),
+    Color::new(0.7, 0.85, 0.7, 0.0),
+];
+
+/// How a burst of particles starts
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Emitter {
+    pub count: usize,
+    /// In pixels per second, from the first to the second
+    pub speed: (f32, f32),
+    /// In radians to each side of the direction of the burst
+    pub spread: f32,
+    /// In seconds, from the first to the second
+    pub lifetime: (f32, f32),
+    pub radius: f32,
+    /// Fraction of the speed lost per second
+    pub drag: f32,
+    /// From birth to death, evenly spaced
+    pub colors: &'static [Color],
+}
+
+pub const EXHAUST: Emitter = Emitter {
+    count: 2,
+    speed: (60.0, 160.0),
+    spread: 0.35,
+    lifetime: (0.2, 0.45),
+    radius: 3.0,
+    drag: 2.0,
+    colors: EXHAUST_COLORS,
+};
+
+pub const EXPLOSION: Emitter = Emitter {
+    count: 120,
+    speed: (80.0, 500.0),
+    spread: std::f32::consts::PI,
+    lifetime: (0.4, 1.1),
+    radius: 4.0,
+    drag: 2.5,
+    colors: EXPLOSION_COLORS,
+};
+
+pub const SPARKS: Emitter = Emitter {
+    count: 16,
+    speed: (100.0, 300.0),
+    spread: std::f32::consts::PI,
+    lifetime: (0.15, 0.35),
+    radius: 2.0,
+    drag: 4.0,
+    colors: SPARK_COLORS,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Particle {
+    pub pos: Vec2,
+    pub vel: Vec2,
+    pub age: f32,
+    pub lifetime: f32,
+    radius: f32,
+    drag: f32,
+    colors: &'static [Color],
+}
+
+impl Particle {
+    pub fn color(&self) -> Color {
+        color_ramp(self.colors, self.age / self.lifetime)
+    }
+}
+
+pub struct Particles {
+    pool: Vec<Particle>,
+    noise: Noise,
+}
+
+impl Particles {
+    pub fn new(capacity: usize, seed: u32) -> Self {
+        Self {
+            pool: Vec::with_capacity(capacity),
+            noise: Noise::new(seed),
+        }
+    }
+
+    /// Particles that don't fit in the pool are not emitted
+    pub fn emit(&mut self, emitter: &Emitter, pos: Vec2, direction: Vec2) {
+        let angle = direction.y.atan2(direction.x);
+        for _ in 0..emitter.count {
+            if self.pool.len() == self.pool.capacity() {
+                return;
+            }
+            let angle = angle + self.noise.sample() * emitter.spread;
+            let speed = self.between(emitter.speed);
+            let lifetime = self.between(emitter.lifetime);
+            self.pool.push(Particle {
+                pos,
+                vel: Vec2::from_angle(angle) * speed,
+                age: 0.0,
+                lifetime,
+                radius: emitter.radius,
+                drag: emitter.drag,
+                colors: emitter.colors,
+            });
+        }
+    }
+
+    fn between(&mut self, (min, max): (f32, f32)) -> f32 {
+        min + (max - min) * (self.noise.sample() * 0.5 + 0.5)
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        for particle in &mut self.pool {
+            particle.age += dt;
+            particle.pos += particle.vel * dt;
+            particle.vel *= (1.0 - particle.drag * dt).max(0.0);
+        }
+        self.pool
+            .retain(|particle| particle.age < particle.lifetime);
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.pool
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pool.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.pool.clear();
+    }
+
+    pub fn render(&self) {
+        for particle in &self.pool {
+            let shrink = 1.0 - 0.5 * particle.age / particle.lifetime;
+            let radius = particle.radius * shrink;
+            draw_circle(particle.pos.x, particle.pos.y, radius, particle.color());
+        }
+    }
+}
+
+/// Interpolates between evenly spaced colors, with `t` from 0 to 1
+pub fn color_ramp(colors: &[Color], t: f32) -> Color {
+    let last = colors.len() - 1;
+    let position = t.clamp(0.0, 1.0) * last as f32;
+    let i = (position as usize).min(last.saturating_sub(1));
+    let (from, to) = (colors[i], colors[(i + 1).min(last)]);
+    let t = position - i as f32;
+    let mix = |from: f32, to: f32| from + (to - from) * t;
+    Color::new(
+        mix(from.r, to.r),
+        mix(from.g, to.g),
+        mix(from.b, to.b),
+        mix(from.a, to.a),
+    )
+}
@@ -0,0 +1,393 @@
+use crate::actions::Bindings;
+use crate::key_names::{key_from_name, key_name};
+use crate::save::{MemoryBackend, Save};
+use crate::settings::Settings;
+use crate::stages::driver::Shared;
+use crate::PixelPosition;
+use juquad::input::input_trait::InputTrait;
+use macroquad::input::{KeyCode, MouseButton};
+use macroquad::math::{vec2, Vec2};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const HEADER: &str = "press-to-start-replay 2";
+const SETTINGS_PREFIX: &str = "settings ";
+const BINDINGS_PREFIX: &str = "bindings ";
+const MOUSE_BUTTONS: [MouseButton; 3] =
+    [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+/// Everything a single simulation step saw from the outside world
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputFrame {
+    pub mouse: Vec2,
+    pub wheel: Vec2,
+    pub buttons_down: u8,
+    pub buttons_pressed: u8,
+    pub buttons_released: u8,
+    pub screen: Vec2,
+    /// Only the keys that the stages asked about, and were true
+    pub keys_down: Vec<KeyCode>,
+    pub keys_pressed: Vec<KeyCode>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recording {
+    /// What the session started with, because they change how the same input plays out
+    pub settings: Settings,
+    pub bindings: Bindings,
+    pub frames: Vec<InputFrame>,
+}
+
+impl Recording {
+    /// The settings and bindings, each line prefixed with what it belongs to, then one line per
+    /// step. Runs of identical steps are written as `*N` to keep idle time cheap.
+    pub fn serialize(&self) -> String {
+        let mut text = String::from(HEADER);
+        text.push('\n');
+        for (prefix, lines) in [
+            (SETTINGS_PREFIX, self.settings.serialize()),
+            (BINDINGS_PREFIX, self.bindings.serialize()),
+        ] {
+            for line in lines.lines() {
+                text.push_str(&format!("{}{}\n", prefix, line));
+            }
+        }
+        let mut previous: Option<&InputFrame> = None;
+        let mut repeated = 0;
+        for frame in &self.frames {
+            if previous == Some(frame) {
+                repeated += 1;
+                continue;
+            }
+            if repeated > 0 {
+                text.push_str(&format!("*{}\n", repeated));
+                repeated = 0;
+            }
+            text.push_str(&serialize_frame(frame));
+            text.push('\n');
+            previous = Some(frame);
+        }
+        if repeated > 0 {
+            text.push_str(&format!("*{}\n", repeated));
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().enumerate();
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            other => return Err(format!("expected header {:?}, got {:?}", HEADER, other)),
+        }
+        let (mut settings, mut bindings) = (String::new(), String::new());
+        let mut frames: Vec<InputFrame> = Vec::new();
+        for (i, line) in lines {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(setting) = line.strip_prefix(SETTINGS_PREFIX) {
+                settings.push_str(setting);
+                settings.push('\n');
+            } else if let Some(binding) = line.strip_prefix(BINDINGS_PREFIX) {
+                bindings.push_str(binding);
+                bindings.push('\n');
+            } else if let Some(count) = line.strip_prefix('*') {
+                let count = count
+                    .parse::<usize>()
+                    .map_err(|e| format!("line {}: {}", i + 1, e))?;
+                let last = frames
+                    .last()
+                    .cloned()
+                    .ok_or_else(|| format!("line {}: repetition without a frame", i + 1))?;
+                frames.resize(frames.len() + count, last);
+            } else {
+                frames.push(parse_frame(line).map_err(|e| format!("line {}: {}", i + 1, e))?);
+            }
+        }
+        Ok(Self {
+            settings: Settings::parse(&settings).map_err(|e| format!("settings: {}", e))?,
+            bindings: Bindings::parse(&bindings).map_err(|e| format!("bindings: {}", e))?,
+            frames,
+        })
+    }
+
+    /// Makes `shared` start like the recorded session did, without touching the files of this
+    /// one: whatever happens during the replay is forgotten when it ends
+    pub fn restore(&self, shared: &mut Shared, input: &Box<dyn InputTrait>) {
+        shared.save = Save::in_memory();
+        shared.settings_backend = Box::new(MemoryBackend::new());
+        shared.bindings_backend = Box::new(MemoryBackend::new());
+        shared.settings = self.settings.clone();
+        shared.bindings = self.bindings.clone();
+        if let Some(language) = &self.settings.language {
+            shared.set_language(language, input);
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.serialize()).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::parse(&text)
+    }
+}
+
+fn serialize_frame(frame: &InputFrame) -> String {
+    format!(
+        "{} {} {} {} {:x} {:x} {:x} {} {} {} {}",
+        frame.mouse.x,
+        frame.mouse.y,
+        frame.wheel.x,
+        frame.wheel.y,
+        frame.buttons_down,
+        frame.buttons_pressed,
+        frame.buttons_released,
+        frame.screen.x,
+        frame.screen.y,
+        serialize_keys(&frame.keys_down),
+        serialize_keys(&frame.keys_pressed),
+    )
+}
+
+fn serialize_keys(keys: &[KeyCode]) -> String {
+    if keys.is_empty() {
+        "-".to_string()
+    } else {
+        keys.iter()
+            .map(|key| key_name(*key))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+fn parse_frame(line: &str) -> Result<InputFrame, String> {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    if words.len() != 11 {
+        return Err(format!("expected 11 fields, got {}", words.len()));
+    }
+    let float = |i: usize| {
+        words[i]
+            .parse::<f32>()
+            .map_err(|e| format!("{}: {}", words[i], e))
+    };
+    let bits =
+        |i: usize| u8::from_str_radix(words[i], 16).map_err(|e| format!("{}: {}", words[i], e));
+    Ok(InputFrame {
+        mouse: vec2(float(0)?, float(1)?),
+        wheel: vec2(float(2)?, float(3)?),
+        buttons_down: bits(4)?,
+        buttons_pressed: bits(5)?,
+        buttons_released: bits(6)?,
+        screen: vec2(float(7)?, float(8)?),
+        keys_down: parse_keys(words[9])?,
+        keys_pressed: parse_keys(words[10])?,
+    })
+}
+
+fn parse_keys(word: &str) -> Result<Vec<KeyCode>, String> {
+    if word == "-" {
+        return Ok(Vec::new());
+    }
+    word.split(',')
+        .map(|name| key_from_name(name).ok_or_else(|| format!("unknown key {}", name)))
+        .collect()
+}
+
+pub fn button_bit(button: MouseButton) -> u8 {
+    match button {
+        MouseButton::Left => 1,
+        MouseButton::Right => 2,
+        MouseButton::Middle => 4,
+        MouseButton::Unknown => 0,
+    }
+}
+
+fn button_bits(is_set: impl Fn(MouseButton) -> bool) -> u8 {
+    MOUSE_BUTTONS
+        .iter()
+        .filter(|button| is_set(**button))
+        .map(|button| button_bit(*button))
+        .sum()
+}
+
+/// Writes down what the game sees, one `InputFrame` per step
+pub struct InputRecorder {
+    settings: Settings,
+    bindings: Bindings,
+    frames: Rc<RefCell<Vec<InputFrame>>>,
+}
+
+impl InputRecorder {
+    /// Starts from these settings and bindings. The language should be the one in use, not
+    /// the system one, which can differ where the recording is played.
+    pub fn new(settings: Settings, bindings: Bindings) -> Self {
+        Self {
+            settings,
+            bindings,
+            frames: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// Starts a new frame and returns the input that the step should use
+    pub fn record_step(&self, input: &Box<dyn InputTrait>, screen: Vec2) -> Box<dyn InputTrait> {
+        self.frames.borrow_mut().push(InputFrame {
+            mouse: input.mouse_position(),
+            wheel: input.mouse_wheel(),
+            buttons_down: button_bits(|b| input.is_mouse_button_down(b)),
+            buttons_pressed: button_bits(|b| input.is_mouse_button_pressed(b)),
+            buttons_released: button_bits(|b| input.is_mouse_button_released(b)),
+            screen,
+            keys_down: Vec::new(),
+            keys_pressed: Vec::new(),
+        });
+        Box::new(RecordingInput {
+            inner: (*input).clone(),
+            frames: self.frames.clone(),
+        })
+    }
+
+    pub fn recording(&self) -> Recording {
+        Recording {
+            settings: self.settings.clone(),
+            bindings: self.bindings.clone(),
+            frames: self.frames.borrow().clone(),
+        }
+    }
+}
+
+/// Forwards to the real input, noting down the keys that were asked about
+struct RecordingInput {
+    inner: Box<dyn InputTrait>,
+    frames: Rc<RefCell<Vec<InputFrame>>>,
+}
+
+impl RecordingInput {
+    fn note(&self, key: KeyCode, is_set: bool, keys: fn(&mut InputFrame) -> &mut Vec<KeyCode>) {
+        if is_set {
+            if let Some(frame) = self.frames.borrow_mut().last_mut() {
+                let keys = keys(frame);
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+    }
+}
+
+impl InputTrait for RecordingInput {
+    fn is_key_down(&self, key: KeyCode) -> bool {
+        let down = self.inner.is_key_down(key);
+        self.note(key, down, |frame| &mut frame.keys_down);
+        down
+    }
+
+    fn is_key_pressed(&self, key: KeyCode) -> bool {
+        let pressed = self.inner.is_key_pressed(key);
+        self.note(key, pressed, |frame| &mut frame.keys_pressed);
+        pressed
+    }
+
+    fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.inner.is_mouse_button_down(button)
+    }
+
+    fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.inner.is_mouse_button_pressed(button)
+    }
+
+    fn is_mouse_button_released(&self, button: MouseButton) -> bool {
+        self.inner.is_mouse_button_released(button)
+    }
+
+    fn mouse_position(&self) -> PixelPosition {
+        self.inner.mouse_position()
+    }
+
+    fn mouse_wheel(&self) -> PixelPosition {
+        self.inner.mouse_wheel()
+    }
+
+    fn clone(&self) -> Box<dyn InputTrait> {
+        Box::new(RecordingInput {
+            inner: self.inner.clone(),
+            frames: self.frames.clone(),
+        })
+    }
+}
+
+/// Feeds a `Recording` back to the game, one frame per step
+pub struct Replayer {
+    frames: Vec<InputFrame>,
+    next: usize,
+    current: Rc<RefCell<InputFrame>>,
+}
+
+impl Replayer {
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            frames: recording.frames,
+            next: 0,
+            current: Rc::new(RefCell::new(InputFrame::default())),
+        }
+    }
+
+    /// Moves to the next frame. Returns None when the recording is over.
+    pub fn next_step(&mut self) -> Option<(Box<dyn InputTrait>, Vec2)> {
+        let frame = self.frames.get(self.next)?;
+        self.next += 1;
+        *self.current.borrow_mut() = frame.clone();
+        let input = Box::new(FrameInput::new(self.current.clone()));
+        Some((input, frame.screen))
+    }
+}
+
+/// Answers everything from an `InputFrame` that someone else updates every step.
+/// All the clones given to widgets see the same frame.
+pub struct FrameInput {
+    frame: Rc<RefCell<InputFrame>>,
+}
+
+impl FrameInput {
+    pub fn new(frame: Rc<RefCell<InputFrame>>) -> Self {
+        Self { frame }
+    }
+}
+
+impl InputTrait for FrameInput {
+    fn is_key_down(&self, key: KeyCode) -> bool {
+        self.frame.borrow().keys_down.contains(&key)
+    }
+
+    fn is_key_pressed(&self, key: KeyCode) -> bool {
+        self.frame.borrow().keys_pressed.contains(&key)
+    }
+
+    fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.frame.borrow().buttons_down & button_bit(button) != 0
+    }
+
+    fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.frame.borrow().buttons_pressed & button_bit(button) != 0
+    }
+
+    fn is_mouse_button_released(&self, button: MouseButton) -> bool {
+        self.frame.borrow().buttons_released & button_bit(button) != 0
+    }
+
+    fn mouse_position(&self) -> PixelPosition {
+        self.frame.borrow().mouse
+    }
+
+    fn mouse_wheel(&self) -> PixelPosition {
+        self.frame.borrow().wheel
+    }
+
+    fn clone(&self) -> Box<dyn InputTrait> {
+        Box::new(FrameInput {
+            frame: self.frame.clone(),
+        })
+    }
+}
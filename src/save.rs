@@ -0,0 +1,255 @@
+//! What survives closing the game: the stages reached, some statistics and the unlocks.
+
+use crate::stages::driver::StageId;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+
+pub const SAVE_PATH: &str = "press-to-start-save.txt";
+const HEADER: &str = "press-to-start-save";
+pub const SAVE_VERSION: u32 = 1;
+
+/// `MIGRATIONS[i]` turns the fields of version `i + 1` into the fields of version `i + 2`.
+/// When changing the format, bump `SAVE_VERSION` and append the migration here, or the build fails.
+const MIGRATIONS: &[fn(&mut Fields)] = &[];
+const _: () = assert!(MIGRATIONS.len() == SAVE_VERSION as usize - 1);
+
+/// The `key value` lines of a save file, before knowing what they mean
+type Fields = BTreeMap<String, String>;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SaveData {
+    pub reached: BTreeSet<StageId>,
+    /// Times a rocket hit the cursor
+    pub deaths: u32,
+    /// Times a rocket hit the button
+    pub buttons_destroyed: u32,
+    pub gave_up: u32,
+    pub escapes: u32,
+    pub unlocks: BTreeSet<String>,
+}
+
+impl SaveData {
+    pub fn has_reached(&self, stage: StageId) -> bool {
+        self.reached.contains(&stage)
+    }
+
+    pub fn is_unlocked(&self, unlock: &str) -> bool {
+        self.unlocks.contains(unlock)
+    }
+
+    pub fn serialize(&self) -> String {
+        let reached = self
+            .reached
+            .iter()
+            .map(|stage| stage.name())
+            .collect::<Vec<_>>();
+        let unlocks = self.unlocks.iter().cloned().collect::<Vec<_>>();
+        let mut text = format!("{} {}\n", HEADER, SAVE_VERSION);
+        for (key, value) in [
+            ("reached", serialize_list(&reached)),
+            ("deaths", self.deaths.to_string()),
+            ("buttons_destroyed", self.buttons_destroyed.to_string()),
+            ("gave_up", self.gave_up.to_string()),
+            ("escapes", self.escapes.to_string()),
+            ("unlocks", serialize_list(&unlocks)),
+        ] {
+            text.push_str(&format!("{} {}\n", key, value));
+        }
+        text
+    }
+
+    /// Accepts older versions, migrating them to the current one
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        let header = lines.next().unwrap_or_default();
+        let version = header
+            .strip_prefix(HEADER)
+            .and_then(|rest| rest.trim().parse::<u32>().ok())
+            .ok_or_else(|| format!("expected header `{} <version>`, got {:?}", HEADER, header))?;
+        if version == 0 || version > SAVE_VERSION {
+            return Err(format!(
+                "unsupported save version {}, this game understands up to {}",
+                version, SAVE_VERSION
+            ));
+        }
+        let mut fields = Fields::new();
+        for (i, line) in lines.enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            if fields.insert(key.to_string(), value.to_string()).is_some() {
+                return Err(format!("line {}: repeated key {}", i + 2, key));
+            }
+        }
+        for migration in &MIGRATIONS[(version - 1) as usize..] {
+            migration(&mut fields);
+        }
+        Self::from_fields(&fields)
+    }
+
+    fn from_fields(fields: &Fields) -> Result<Self, String> {
+        let count = |key: &str| -> Result<u32, String> {
+            match fields.get(key) {
+                Some(value) => value
+                    .parse()
+                    .map_err(|e| format!("{} {}: {}", key, value, e)),
+                None => Ok(0),
+            }
+        };
+        let list = |key: &str| parse_list(fields.get(key).map_or("", |value| value.as_str()));
+        let reached = list("reached")
+            .iter()
+            .map(|name| StageId::from_name(name).ok_or_else(|| format!("unknown stage {}", name)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            reached,
+            deaths: count("deaths")?,
+            buttons_destroyed: count("buttons_destroyed")?,
+            gave_up: count("gave_up")?,
+            escapes: count("escapes")?,
+            unlocks: list("unlocks").into_iter().collect(),
+        })
+    }
+}
+
+fn serialize_list<S: AsRef<str>>(items: &[S]) -> String {
+    if items.is_empty() {
+        "-".to_string()
+    } else {
+        items
+            .iter()
+            .map(|item| item.as_ref())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    if value.is_empty() || value == "-" {
+        Vec::new()
+    } else {
+        value.split(',').map(|item| item.to_string()).collect()
+    }
+}
+
+/// Where the save file lives
+pub trait SaveBackend {
+    /// None if nothing was saved yet
+    fn read(&self) -> Result<Option<String>, String>;
+    fn write(&mut self, text: &str) -> Result<(), String>;
+}
+
+pub struct FileBackend {
+    path: String,
+}
+
+impl FileBackend {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+        }
+    }
+}
+
+impl SaveBackend for FileBackend {
+    fn read(&self) -> Result<Option<String>, String> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(text) => Ok(Some(text)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("{}: {}", self.path, e)),
+        }
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        std::fs::write(&self.path, text).map_err(|e| format!("{}: {}", self.path, e))
+    }
+}
+
+/// Keeps the save in memory. Clones share the same text, so tests can look at what was written.
+#[derive(Clone, Default)]
+pub struct MemoryBackend {
+    text: Rc<RefCell<Option<String>>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(&self) -> Option<String> {
+        self.text.borrow().clone()
+    }
+}
+
+impl SaveBackend for MemoryBackend {
+    fn read(&self) -> Result<Option<String>, String> {
+        Ok(self.text())
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        *self.text.borrow_mut() = Some(text.to_string());
+        Ok(())
+    }
+}
+
+/// The save data together with where to store it. Every change is written right away.
+pub struct Save {
+    data: SaveData,
+    backend: Box<dyn SaveBackend>,
+}
+
+impl Save {
+    /// Starts from scratch if there is no save yet. If there is one that can't be understood,
+    /// like one from a newer version of the game, it is left untouched and nothing is saved.
+    pub fn load(backend: Box<dyn SaveBackend>) -> Self {
+        match backend
+            .read()
+            .and_then(|text| text.map(|t| SaveData::parse(&t)).transpose())
+        {
+            Ok(data) => Self {
+                data: data.unwrap_or_default(),
+                backend,
+            },
+            Err(e) => {
+                println!("could not load the save, progress will not be saved: {}", e);
+                Self::in_memory()
+            }
+        }
+    }
+
+    pub fn in_memory() -> Self {
+        Self::load(Box::new(MemoryBackend::new()))
+    }
+
+    pub fn data(&self) -> &SaveData {
+        &self.data
+    }
+
+    pub fn update(&mut self, change: impl FnOnce(&mut SaveData)) {
+        let before = self.data.clone();
+        change(&mut self.data);
+        if self.data != before {
+            if let Err(e) = self.backend.write(&self.data.serialize()) {
+                println!("could not write the save: {}", e);
+            }
+        }
+    }
+
+    pub fn reach(&mut self, stage: StageId) {
+        self.update(|data| {
+            data.reached.insert(stage);
+        });
+    }
+
+    /// Returns true if it was not unlocked before
+    pub fn unlock(&mut self, unlock: &str) -> bool {
+        let new = !self.data.is_unlocked(unlock);
+        self.update(|data| {
+            data.unlocks.insert(unlock.to_string());
+        });
+        new
+    }
+}
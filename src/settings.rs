@@ -0,0 +1,189 @@
+//! Options that the player chooses in the settings menu, kept between sessions.
+
+use crate::audio::Volumes;
+use crate::save::SaveBackend;
+
+pub const SETTINGS_PATH: &str = "press-to-start-settings.txt";
+pub const PRISON_SENSITIVITIES: [f32; 5] = [0.5, 0.75, 1.0, 1.5, 2.0];
+pub const VOLUME_STEPS: [f32; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn id(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+    fn from_id(id: &str) -> Option<Self> {
+        [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+            .into_iter()
+            .find(|d| d.id() == id)
+    }
+    pub fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+    /// Multiplies the speed of the rockets
+    pub fn speed_factor(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.3,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WindowMode {
+    Windowed,
+    Fullscreen,
+}
+
+impl WindowMode {
+    pub fn id(self) -> &'static str {
+        match self {
+            WindowMode::Windowed => "windowed",
+            WindowMode::Fullscreen => "fullscreen",
+        }
+    }
+    fn from_id(id: &str) -> Option<Self> {
+        [WindowMode::Windowed, WindowMode::Fullscreen]
+            .into_iter()
+            .find(|mode| mode.id() == id)
+    }
+    pub fn next(self) -> Self {
+        match self {
+            WindowMode::Windowed => WindowMode::Fullscreen,
+            WindowMode::Fullscreen => WindowMode::Windowed,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// None means the language of the system
+    pub language: Option<String>,
+    pub difficulty: Difficulty,
+    /// Skips fades and pulsing borders
+    pub reduced_motion: bool,
+    /// How far the grabbed prison cursor moves for each pixel the mouse moves
+    pub prison_sensitivity: f32,
+    pub window_mode: WindowMode,
+    pub volumes: Volumes,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            language: None,
+            difficulty: Difficulty::Normal,
+            reduced_motion: false,
+            prison_sensitivity: 1.0,
+            window_mode: WindowMode::Windowed,
+            volumes: Volumes::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn next_prison_sensitivity(&self) -> f32 {
+        next_step(&PRISON_SENSITIVITIES, self.prison_sensitivity)
+    }
+
+    /// `key = value` per line
+    pub fn serialize(&self) -> String {
+        let language = self.language.as_deref().unwrap_or("system");
+        format!(
+            "language = {}\ndifficulty = {}\nreduced_motion = {}\nprison_sensitivity = {}\nwindow_mode = {}\n\
+            master_volume = {}\neffects_volume = {}\ndialog_volume = {}\n",
+            language,
+            self.difficulty.id(),
+            self.reduced_motion,
+            self.prison_sensitivity,
+            self.window_mode.id(),
+            self.volumes.master,
+            self.volumes.effects,
+            self.volumes.dialog,
+        )
+    }
+
+    /// Missing keys keep their default values
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut settings = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", i + 1, message);
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `key = value`".to_string()))?;
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || error(format!("invalid {}: {}", key, value));
+            match key {
+                "language" => {
+                    settings.language = (value != "system").then(|| value.to_string());
+                }
+                "difficulty" => {
+                    settings.difficulty = Difficulty::from_id(value).ok_or_else(invalid)?;
+                }
+                "reduced_motion" => {
+                    settings.reduced_motion = value.parse().map_err(|_| invalid())?;
+                }
+                "prison_sensitivity" => {
+                    let sensitivity = value.parse::<f32>().ok().filter(|s| *s > 0.0);
+                    settings.prison_sensitivity = sensitivity.ok_or_else(invalid)?;
+                }
+                "window_mode" => {
+                    settings.window_mode = WindowMode::from_id(value).ok_or_else(invalid)?;
+                }
+                "master_volume" | "effects_volume" | "dialog_volume" => {
+                    let volume = value.parse::<f32>().ok();
+                    let volume = volume.filter(|v| (0.0..=1.0).contains(v));
+                    let volume = volume.ok_or_else(invalid)?;
+                    let volumes = &mut settings.volumes;
+                    match key {
+                        "master_volume" => volumes.master = volume,
+                        "effects_volume" => volumes.effects = volume,
+                        _ => volumes.dialog = volume,
+                    }
+                }
+                _ => return Err(error(format!("unknown setting {}", key))),
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Uses the defaults if there is no file yet or it has a mistake
+    pub fn load(backend: &dyn SaveBackend) -> Self {
+        match backend.read() {
+            Ok(Some(text)) => Self::parse(&text).unwrap_or_else(|e| {
+                println!("could not parse the settings, using the defaults: {}", e);
+                Self::default()
+            }),
+            Ok(None) => Self::default(),
+            Err(e) => {
+                println!("could not read the settings, using the defaults: {}", e);
+                Self::default()
+            }
+        }
+    }
+}
+
+/// The value after `current` in `steps`, going back to the first one after the last
+pub fn next_step(steps: &[f32], current: f32) -> f32 {
+    let position = steps.iter().position(|step| *step == current);
+    steps[position.map_or(0, |i| (i + 1) % steps.len())]
+}
@@ -0,0 +1,594 @@
+use crate::achievements::{Achievement, Toasts};
+use crate::actions::{Action, Bindings, BINDINGS_PATH};
+use crate::audio::{AudioBackend, MacroquadBackend, Mixer, NullBackend, SoundBank};
+use crate::dev_overlay::DevOverlay;
+use crate::dialog::{load_dialogs, Dialogs};
+use crate::forces::{load_forces, ForceFields, Probe};
+use crate::launcher::{load_launch_rules, LaunchRules};
+use crate::menus::pause::PauseMenu;
+use crate::menus::settings::SettingsMenu;
+use crate::replay::{InputRecorder, Recording, Replayer};
+use crate::save::{FileBackend, MemoryBackend, Save, SaveBackend, SAVE_PATH};
+use crate::settings::{Settings, WindowMode, SETTINGS_PATH};
+use crate::stages::end_screen::{EndScreen, EndScreenStage};
+use crate::stages::prison::PrisonStage;
+use crate::stages::rockets::RocketsStage;
+use crate::stages::torus::TorusStage;
+use crate::strings::{load_strings, Strings};
+use crate::synth::SAMPLE_RATE;
+use crate::timestep::{has_events, FixedTimestep, HeldInput, FIXED_DT};
+use crate::torus_space::Topology;
+use crate::waves::{load_waves, WaveMixes};
+use crate::{new_button_generic, MeasureText};
+use juquad::input::input_macroquad::InputMacroquad;
+use juquad::input::input_trait::InputTrait;
+use juquad::widgets::anchor::Anchor;
+use juquad::widgets::button::Button;
+use juquad::widgets::Widget;
+use macroquad::math::{vec2, Rect, Vec2};
+use macroquad::prelude::{
+    get_frame_time, get_last_key_pressed, measure_text, next_frame, screen_height, screen_width,
+    set_cursor_grab, set_fullscreen, show_mouse,
+};
+use std::collections::HashMap;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum StageId {
+    Torus,
+    Cylinder,
+    MirrorWalls,
+    KleinBottle,
+    ProjectivePlane,
+    Rockets,
+    Prison,
+    GameOver,
+    GameWon,
+}
+
+/// The variants of the wrap stage
+const WRAP_STAGE_IDS: [StageId; 5] = [
+    StageId::Torus,
+    StageId::Cylinder,
+    StageId::MirrorWalls,
+    StageId::KleinBottle,
+    StageId::ProjectivePlane,
+];
+
+const ALL_STAGE_IDS: [StageId; 9] = [
+    StageId::Torus,
+    StageId::Cylinder,
+    StageId::MirrorWalls,
+    StageId::KleinBottle,
+    StageId::ProjectivePlane,
+    StageId::Rockets,
+    StageId::Prison,
+    StageId::GameOver,
+    StageId::GameWon,
+];
+
+impl StageId {
+    /// Stable names for files that outlive the code, like saves
+    pub fn name(self) -> &'static str {
+        match self {
+            StageId::Torus => "torus",
+            StageId::Cylinder => "cylinder",
+            StageId::MirrorWalls => "mirror_walls",
+            StageId::KleinBottle => "klein_bottle",
+            StageId::ProjectivePlane => "projective_plane",
+            StageId::Rockets => "rockets",
+            StageId::Prison => "prison",
+            StageId::GameOver => "game_over",
+            StageId::GameWon => "game_won",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        ALL_STAGE_IDS.into_iter().find(|id| id.name() == name)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StageOutcome {
+    /// The stage finished normally and the campaign should go on
+    Next,
+    Won,
+    Lost,
+    Restart,
+    /// Always ends the game, whatever the graph says
+    Quit,
+    /// The player chose to quit in a stage that doesn't let them leave
+    TriedToQuit,
+    /// The player accepted being stuck in the prison and left
+    GaveUp,
+    /// The player clicked the button while imprisoned
+    Escaped,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Transition {
+    To(StageId),
+    Exit,
+}
+
+/// State that survives across stages, like the button that the player keeps chasing.
+///
+/// Nothing in here talks to the window, so stages can be stepped without one.
+pub struct Shared {
+    pub button: Button,
+    /// The key in `strings` of the button's text. Needed to build copies of the button that use
+    /// other inputs, and to rebuild it when the language changes.
+    pub button_key: &'static str,
+    pub screen: Vec2,
+    pub measure_text: MeasureText,
+    /// Requested by the stages, applied by whoever owns the window
+    pub cursor_grabbed: bool,
+    pub dialogs: Dialogs,
+    pub forces: ForceFields,
+    pub waves: WaveMixes,
+    pub launch_rules: LaunchRules,
+    pub strings: Strings,
+    pub save: Save,
+    pub toasts: Toasts,
+    pub bindings: Bindings,
+    /// Where the rebinding screen writes the bindings
+    pub bindings_backend: Box<dyn SaveBackend>,
+    pub settings: Settings,
+    pub settings_backend: Box<dyn SaveBackend>,
+    pub audio: Mixer,
+    /// Whether the developer overlay is drawn on top of the stage
+    pub dev_overlay: bool,
+}
+impl Shared {
+    pub fn new(screen: Vec2, measure_text: MeasureText, input: &Box<dyn InputTrait>) -> Self {
+        let button_key = "start";
+        let strings = Strings::embedded();
+        let anchor = Anchor::center_v(screen * 0.5);
+        Self {
+            button: new_button_generic(strings.get(button_key), anchor, input, measure_text),
+            button_key,
+            screen,
+            measure_text,
+            cursor_grabbed: false,
+            dialogs: Dialogs::embedded(),
+            forces: ForceFields::embedded(),
+            waves: WaveMixes::embedded(),
+            launch_rules: LaunchRules::embedded(),
+            strings,
+            save: Save::in_memory(),
+            toasts: Toasts::new(),
+            bindings: Bindings::default(),
+            bindings_backend: Box::new(MemoryBackend::new()),
+            settings: Settings::default(),
+            settings_backend: Box::new(MemoryBackend::new()),
+            audio: Mixer::new(),
+            dev_overlay: false,
+        }
+    }
+    pub fn is_pressed(&self, action: Action, input: &Box<dyn InputTrait>) -> bool {
+        self.bindings.is_pressed(action, input)
+    }
+    pub fn save_bindings(&mut self) {
+        if let Err(e) = self.bindings_backend.write(&self.bindings.serialize()) {
+            println!("could not write the key bindings: {}", e);
+        }
+    }
+    pub fn save_settings(&mut self) {
+        if let Err(e) = self.settings_backend.write(&self.settings.serialize()) {
+            println!("could not write the settings: {}", e);
+        }
+    }
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings.get(key)
+    }
+    /// Saves the achievement and shows a toast, unless it was already unlocked
+    pub fn achieve(&mut self, achievement: Achievement) {
+        if self.save.unlock(achievement.id()) {
+            self.toasts.push(achievement);
+        }
+    }
+    /// Builds the shared button with the text of `key`, centered at the same place
+    pub fn set_button_key(&mut self, key: &'static str, input: &Box<dyn InputTrait>) {
+        let anchor = Anchor::center_v(self.button.rect().center());
+        self.button_key = key;
+        self.button = self.new_button(self.text(key), anchor, input);
+    }
+    /// Widgets are sized for their text when built, so the button is rebuilt in the new
+    /// language. Stages rebuild their own widgets every step.
+    pub fn set_language(&mut self, code: &str, input: &Box<dyn InputTrait>) {
+        if self.strings.set_language(code) {
+            self.set_button_key(self.button_key, input);
+        }
+    }
+    /// Like `set_language`, but remembered for the next sessions
+    pub fn choose_language(&mut self, code: &str, input: &Box<dyn InputTrait>) {
+        self.set_language(code, input);
+        self.settings.language = Some(self.strings.language().to_string());
+        self.save_settings();
+    }
+    pub fn new_button(&self, text: &str, anchor: Anchor, input: &Box<dyn InputTrait>) -> Button {
+        new_button_generic(text, anchor, input, self.measure_text)
+    }
+    pub fn screen_center(&self) -> Vec2 {
+        self.screen * 0.5
+    }
+
+    /// How far the fields of `stage` move the button in `dt` seconds
+    pub fn push_button(&self, stage: &str, mouse: Vec2, home: Vec2, time: f32, dt: f32) -> Vec2 {
+        let probe = Probe {
+            pos: self.button.rect().center(),
+            mouse,
+            home,
+            bounds: Rect::new(0.0, 0.0, self.screen.x, self.screen.y),
+            time,
+        };
+        self.forces.velocity(stage, &probe) * dt
+    }
+}
+
+pub enum MenuOutcome {
+    Close,
+    Open(Box<dyn Menu>),
+    /// Closes every menu and enters the current stage again
+    RestartStage,
+    /// Closes every menu and ends the game, unless the graph traps the current stage
+    QuitToDesktop,
+}
+
+/// Shown on top of the current stage, which does not advance while any menu is open
+pub trait Menu {
+    fn step(
+        &mut self,
+        shared: &mut Shared,
+        input: &Box<dyn InputTrait>,
+        dt: f32,
+    ) -> Option<MenuOutcome>;
+    fn render(&self, shared: &Shared);
+}
+
+pub trait Stage {
+    fn enter(&mut self, _shared: &mut Shared, _input: &Box<dyn InputTrait>) {}
+    /// Advances the simulation by `dt` seconds. Returns Some when the stage is done.
+    fn step(
+        &mut self,
+        shared: &mut Shared,
+        input: &Box<dyn InputTrait>,
+        dt: f32,
+    ) -> Option<StageOutcome>;
+    fn render(&self, shared: &Shared);
+    /// Adds what the developer overlay should show about this stage
+    fn inspect(&self, _shared: &Shared, _overlay: &mut DevOverlay) {}
+    /// Called when the last menu closes, after the stage was frozen for a while
+    fn resume(&mut self, _shared: &mut Shared, _input: &Box<dyn InputTrait>) {}
+    /// Whether quitting ends the game even though the graph traps this stage
+    fn lets_quit(&self) -> bool {
+        false
+    }
+    fn exit(&mut self, _shared: &mut Shared) {}
+}
+
+pub fn create_stage(id: StageId) -> Box<dyn Stage> {
+    match id {
+        StageId::Torus => Box::new(TorusStage::new(Topology::Torus)),
+        StageId::Cylinder => Box::new(TorusStage::new(Topology::Cylinder)),
+        StageId::MirrorWalls => Box::new(TorusStage::new(Topology::MirrorWalls)),
+        StageId::KleinBottle => Box::new(TorusStage::new(Topology::KleinBottle)),
+        StageId::ProjectivePlane => Box::new(TorusStage::new(Topology::ProjectivePlane)),
+        StageId::Rockets => Box::new(RocketsStage::new()),
+        StageId::Prison => Box::new(PrisonStage::new()),
+        StageId::GameOver => Box::new(EndScreenStage::new(EndScreen::game_over())),
+        StageId::GameWon => Box::new(EndScreenStage::new(EndScreen::game_won())),
+    }
+}
+
+/// Outcomes that are not listed in the graph end the game
+pub struct StageGraph {
+    pub start: StageId,
+    transitions: HashMap<(StageId, StageOutcome), Transition>,
+}
+impl StageGraph {
+    pub fn new(start: StageId) -> Self {
+        Self {
+            start,
+            transitions: HashMap::new(),
+        }
+    }
+    pub fn with(mut self, from: StageId, outcome: StageOutcome, transition: Transition) -> Self {
+        self.transitions.insert((from, outcome), transition);
+        self
+    }
+    pub fn next(&self, from: StageId, outcome: StageOutcome) -> Transition {
+        if outcome == StageOutcome::Quit {
+            return Transition::Exit;
+        }
+        *self
+            .transitions
+            .get(&(from, outcome))
+            .unwrap_or(&Transition::Exit)
+    }
+
+    /// Whether quitting from `stage` leads somewhere else instead of ending the game
+    pub fn traps(&self, stage: StageId) -> bool {
+        self.transitions
+            .contains_key(&(stage, StageOutcome::TriedToQuit))
+    }
+
+    pub fn campaign() -> Self {
+        use StageId::*;
+        use StageOutcome::*;
+        use Transition::To;
+        Self::new(Torus)
+            .with(Torus, Next, To(Cylinder))
+            .with(Cylinder, Next, To(MirrorWalls))
+            .with(MirrorWalls, Next, To(KleinBottle))
+            .with(KleinBottle, Next, To(ProjectivePlane))
+            .with(ProjectivePlane, Next, To(Rockets))
+            .with(Rockets, Lost, To(GameOver))
+            .with(Rockets, Won, To(GameWon))
+            .with(Rockets, Restart, To(Rockets))
+            .with(Rockets, TriedToQuit, To(Prison))
+            .with(Prison, TriedToQuit, To(Prison))
+            .with(Prison, Escaped, To(Rockets))
+            .with(Prison, Restart, To(Torus))
+            .with(GameOver, Restart, To(Rockets))
+            .with(GameWon, Restart, To(Rockets))
+            .with(GameWon, Next, To(Torus))
+    }
+}
+
+/// Runs the stage graph one step at a time, without knowing if there is a window
+pub struct Simulation {
+    graph: StageGraph,
+    current: Option<(StageId, Box<dyn Stage>)>,
+    /// The last one is the one the player interacts with
+    menus: Vec<Box<dyn Menu>>,
+    /// Every stage entered so far, with the outcome that made it finish
+    pub history: Vec<(StageId, Option<StageOutcome>)>,
+    pub shared: Shared,
+}
+impl Simulation {
+    pub fn new(graph: StageGraph, shared: Shared) -> Self {
+        Self {
+            graph,
+            current: None,
+            menus: Vec::new(),
+            history: Vec::new(),
+            shared,
+        }
+    }
+    pub fn current(&self) -> Option<StageId> {
+        self.current.as_ref().map(|(id, _)| *id)
+    }
+    pub fn is_finished(&self) -> bool {
+        self.current.is_none() && !self.history.is_empty()
+    }
+    pub fn is_paused(&self) -> bool {
+        !self.menus.is_empty()
+    }
+    /// The menus need the real pointer, even in stages that grab it
+    pub fn cursor_grabbed(&self) -> bool {
+        self.shared.cursor_grabbed && !self.is_paused()
+    }
+    /// Returns false once the graph reached `Transition::Exit`
+    pub fn step(&mut self, input: &Box<dyn InputTrait>, dt: f32) -> bool {
+        if self.current.is_none() {
+            if self.is_finished() {
+                return false;
+            }
+            self.enter(self.graph.start, input);
+        }
+        self.shared.toasts.step(dt);
+        self.shared.audio.step(dt);
+        if let Some(menu) = self.menus.last_mut() {
+            match menu.step(&mut self.shared, input, dt) {
+                Some(MenuOutcome::Close) => {
+                    self.menus.pop();
+                    if let (true, Some((_, stage))) = (self.menus.is_empty(), &mut self.current) {
+                        stage.resume(&mut self.shared, input);
+                    }
+                }
+                Some(MenuOutcome::Open(menu)) => self.menus.push(menu),
+                Some(MenuOutcome::RestartStage) => {
+                    self.menus.clear();
+                    let id = self.finish(StageOutcome::Restart);
+                    self.enter(id, input);
+                }
+                Some(MenuOutcome::QuitToDesktop) => {
+                    self.menus.clear();
+                    let trapped = self
+                        .current
+                        .as_ref()
+                        .is_some_and(|(id, stage)| self.graph.traps(*id) && !stage.lets_quit());
+                    let outcome = if trapped {
+                        StageOutcome::TriedToQuit
+                    } else {
+                        StageOutcome::Quit
+                    };
+                    return self.transition(outcome, input);
+                }
+                None => {}
+            }
+            return true;
+        }
+        if self.shared.is_pressed(Action::SwitchLanguage, input) {
+            let next = self.shared.strings.next_language().to_string();
+            self.shared.choose_language(&next, input);
+        }
+        if self.shared.is_pressed(Action::DevOverlay, input) {
+            self.shared.dev_overlay = !self.shared.dev_overlay;
+        }
+        if self.shared.is_pressed(Action::Settings, input) {
+            self.menus.push(Box::new(SettingsMenu::new()));
+            return true;
+        }
+        if self.shared.is_pressed(Action::Quit, input)
+            || self.shared.is_pressed(Action::Pause, input)
+        {
+            self.menus.push(Box::new(PauseMenu::new()));
+            return true;
+        }
+        let (_, stage) = self.current.as_mut().unwrap();
+        if let Some(outcome) = stage.step(&mut self.shared, input, dt) {
+            return self.transition(outcome, input);
+        }
+        true
+    }
+    /// Leaves the current stage for the one that the graph chooses
+    fn transition(&mut self, outcome: StageOutcome, input: &Box<dyn InputTrait>) -> bool {
+        let id = self.finish(outcome);
+        match self.graph.next(id, outcome) {
+            Transition::To(next) => {
+                self.enter(next, input);
+                true
+            }
+            Transition::Exit => false,
+        }
+    }
+    /// Exits the current stage and returns which one it was
+    fn finish(&mut self, outcome: StageOutcome) -> StageId {
+        let (id, mut stage) = self.current.take().unwrap();
+        stage.exit(&mut self.shared);
+        if let Some(last) = self.history.last_mut() {
+            last.1 = Some(outcome);
+        }
+        id
+    }
+    /// What the developer overlay shows of the current stage, if it's enabled
+    pub fn dev_overlay(&self) -> Option<DevOverlay> {
+        let (id, stage) = self.current.as_ref().filter(|_| self.shared.dev_overlay)?;
+        let mut overlay = DevOverlay::new();
+        overlay.line(format!("stage: {}", id.name()));
+        stage.inspect(&self.shared, &mut overlay);
+        Some(overlay)
+    }
+    pub fn render(&self) {
+        if let Some((_, stage)) = &self.current {
+            stage.render(&self.shared);
+        }
+        if let Some(overlay) = self.dev_overlay() {
+            overlay.render(&self.shared);
+        }
+        for menu in &self.menus {
+            menu.render(&self.shared);
+        }
+        let shared = &self.shared;
+        shared.toasts.render(&shared.strings, shared.screen);
+    }
+    fn enter(&mut self, id: StageId, input: &Box<dyn InputTrait>) {
+        self.shared.save.reach(id);
+        let reached = |id: &StageId| self.shared.save.data().has_reached(*id);
+        if WRAP_STAGE_IDS.iter().all(reached) {
+            self.shared.achieve(Achievement::Cartographer);
+        }
+        let mut stage = create_stage(id);
+        stage.enter(&mut self.shared, input);
+        self.history.push((id, None));
+        self.current = Some((id, stage));
+    }
+}
+
+pub enum InputMode {
+    Live,
+    /// Saves the input of every step to this path when the game exits
+    Record(String),
+    /// Plays back the steps and then gives control to the player
+    Replay(Recording),
+}
+
+pub async fn run_stages(graph: StageGraph, mode: InputMode) {
+    let input: Box<dyn InputTrait> = Box::new(InputMacroquad);
+    let held_input: Box<dyn InputTrait> = Box::new(HeldInput::new(&input));
+    let screen = match &mode {
+        InputMode::Replay(recording) => recording.frames.first().map(|frame| frame.screen),
+        _ => None,
+    };
+    let screen = screen.unwrap_or_else(|| vec2(screen_width(), screen_height()));
+    let mut shared = Shared::new(screen, measure_text, &input);
+    shared.dialogs = load_dialogs().await;
+    shared.forces = load_forces().await;
+    shared.waves = load_waves().await;
+    shared.launch_rules = load_launch_rules().await;
+    shared.save = Save::load(Box::new(FileBackend::new(SAVE_PATH)));
+    shared.bindings_backend = Box::new(FileBackend::new(BINDINGS_PATH));
+    shared.bindings = Bindings::load(shared.bindings_backend.as_ref());
+    shared.settings_backend = Box::new(FileBackend::new(SETTINGS_PATH));
+    shared.settings = Settings::load(shared.settings_backend.as_ref());
+    let strings = load_strings().await;
+    let language = shared
+        .settings
+        .language
+        .clone()
+        .unwrap_or_else(|| strings.language().to_string());
+    shared.strings = strings;
+    shared.set_language(&language, &input);
+    let (mut replayer, recorder, record_path) = match mode {
+        InputMode::Live => (None, None, None),
+        InputMode::Record(path) => {
+            let mut settings = shared.settings.clone();
+            settings.language = Some(shared.strings.language().to_string());
+            let recorder = InputRecorder::new(settings, shared.bindings.clone());
+            (None, Some(recorder), Some(path))
+        }
+        InputMode::Replay(recording) => {
+            recording.restore(&mut shared, &input);
+            (Some(Replayer::new(recording)), None, None)
+        }
+    };
+    let bank = SoundBank::synthesize(SAMPLE_RATE);
+    let mut audio: Box<dyn AudioBackend> = match MacroquadBackend::load(&bank).await {
+        Ok(backend) => Box::new(backend),
+        Err(e) => {
+            println!("could not load the sounds, the game will be silent: {}", e);
+            Box::new(NullBackend::default())
+        }
+    };
+    let mut simulation = Simulation::new(graph, shared);
+    let mut timestep = FixedTimestep::new();
+    let mut cursor_grabbed = false;
+    let mut window_mode = WindowMode::Windowed;
+    'frames: loop {
+        let key_pressed = get_last_key_pressed().is_some();
+        let steps = timestep.advance(get_frame_time(), has_events(&input, key_pressed));
+        for i in 0..steps {
+            let mut screen = vec2(screen_width(), screen_height());
+            let replayed = replayer.as_mut().and_then(|replayer| replayer.next_step());
+            let step_input = if let Some((replayed_input, replayed_screen)) = replayed {
+                screen = replayed_screen;
+                replayed_input
+            } else {
+                if replayer.take().is_some() {
+                    println!("replay finished, the player has control now");
+                }
+                let live_input = if i == 0 { &input } else { &held_input };
+                (*live_input).clone()
+            };
+            let step_input = match &recorder {
+                Some(recorder) => recorder.record_step(&step_input, screen),
+                None => step_input,
+            };
+            simulation.shared.screen = screen;
+            if !simulation.step(&step_input, FIXED_DT) {
+                break 'frames;
+            }
+        }
+        if simulation.cursor_grabbed() != cursor_grabbed {
+            cursor_grabbed = simulation.cursor_grabbed();
+            set_cursor_grab(cursor_grabbed);
+            show_mouse(!cursor_grabbed);
+        }
+        if simulation.shared.settings.window_mode != window_mode {
+            window_mode = simulation.shared.settings.window_mode;
+            set_fullscreen(window_mode == WindowMode::Fullscreen);
+        }
+        let shared = &mut simulation.shared;
+        shared.audio.flush(&shared.settings.volumes, audio.as_mut());
+        simulation.render();
+        next_frame().await;
+    }
+    set_cursor_grab(false);
+    show_mouse(true);
+    if let (Some(recorder), Some(path)) = (recorder, record_path) {
+        match recorder.recording().save(&path) {
+            Ok(()) => println!("saved input recording to {}", path),
+            Err(e) => println!("could not save input recording: {}", e),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::actions::Action;
+use crate::audio::SoundEffect;
+use crate::dev_overlay::{DevOverlay, Hitbox};
+use crate::geometry::Shape;
+use crate::stages::driver::{Shared, Stage, StageOutcome};
+use crate::{render_button, FONT_SIZE, LIGHT_GREEN};
+use juquad::draw::draw_rect;
+use juquad::input::input_trait::InputTrait;
+use juquad::widgets::anchor::{Anchor, Horizontal};
+use juquad::widgets::button::Button;
+use juquad::widgets::text::TextRect;
+use juquad::widgets::{StateStyle, Widget};
+use macroquad::color::{Color, LIGHTGRAY, RED};
+use macroquad::prelude::clear_background;
+
+pub const TRANSPARENT: Color = Color::new(0.0, 0.0, 0.0, 0.0);
+pub const GAME_OVER_STYLE: StateStyle = StateStyle {
+    bg_color: TRANSPARENT,
+    text_color: RED,
+    border_color: TRANSPARENT,
+};
+pub const GAME_WON_STYLE: StateStyle = StateStyle {
+    bg_color: TRANSPARENT,
+    text_color: LIGHT_GREEN,
+    border_color: TRANSPARENT,
+};
+pub const ALMOST_BLACK: Color = Color::new(0.2, 0.2, 0.2, 1.0);
+/// From LIGHTGRAY to ALMOST_BLACK at the 0.42 per second that the old screens used
+pub const FADE_SECONDS: f32 = 1.4;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EndButton {
+    Restart,
+    NextStage,
+    Exit,
+}
+
+impl EndButton {
+    fn text_key(self) -> &'static str {
+        match self {
+            EndButton::Restart => "restart",
+            EndButton::NextStage => "next_stage",
+            EndButton::Exit => "exit",
+        }
+    }
+    fn outcome(self) -> StageOutcome {
+        match self {
+            EndButton::Restart => StageOutcome::Restart,
+            EndButton::NextStage => StageOutcome::Next,
+            EndButton::Exit => StageOutcome::Quit,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FadeCurve {
+    Linear,
+    /// Starts slow and ends fast
+    EaseIn,
+    /// Starts fast and ends slow
+    EaseOut,
+}
+
+impl FadeCurve {
+    /// Maps the progress in [0, 1] to how much of the fade is done, also in [0, 1]
+    pub fn apply(self, progress: f32) -> f32 {
+        let x = progress.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => x,
+            FadeCurve::EaseIn => x * x,
+            FadeCurve::EaseOut => 1.0 - (1.0 - x) * (1.0 - x),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Fade {
+    pub from: Color,
+    pub to: Color,
+    pub seconds: f32,
+    pub curve: FadeCurve,
+}
+
+impl Fade {
+    pub fn color(&self, time: f32) -> Color {
+        let t = self.curve.apply(time / self.seconds);
+        let mix = |from: f32, to: f32| from + (to - from) * t;
+        Color::new(
+            mix(self.from.r, self.to.r),
+            mix(self.from.g, self.to.g),
+            mix(self.from.b, self.to.b),
+            mix(self.from.a, self.to.a),
+        )
+    }
+}
+
+/// Everything that makes an end screen different from the others
+#[derive(Clone, Debug, PartialEq)]
+pub struct EndScreen {
+    pub title_key: &'static str,
+    pub title_style: StateStyle,
+    pub subtitle_key: Option<&'static str>,
+    pub subtitle_style: StateStyle,
+    /// From top to bottom. The restart key is a shortcut for Restart.
+    pub buttons: Vec<EndButton>,
+    pub fade: Fade,
+    /// Played when the screen appears
+    pub sound: Option<SoundEffect>,
+}
+
+impl EndScreen {
+    pub fn game_over() -> Self {
+        Self {
+            title_key: "you_died",
+            title_style: GAME_OVER_STYLE,
+            subtitle_key: None,
+            subtitle_style: GAME_OVER_STYLE,
+            buttons: vec![EndButton::Restart, EndButton::Exit],
+            fade: Fade {
+                from: LIGHTGRAY,
+                to: ALMOST_BLACK,
+                seconds: FADE_SECONDS,
+                curve: FadeCurve::Linear,
+            },
+            sound: Some(SoundEffect::DeathSting),
+        }
+    }
+
+    pub fn game_won() -> Self {
+        Self {
+            title_key: "button_destroyed",
+            title_style: GAME_WON_STYLE,
+            subtitle_key: Some("no_start_button"),
+            buttons: vec![EndButton::NextStage, EndButton::Restart, EndButton::Exit],
+            // the rockets stage already played the explosion
+            sound: None,
+            ..Self::game_over()
+        }
+    }
+}
+
+pub struct EndScreenStage {
+    config: EndScreen,
+    time: f32,
+    buttons: Vec<Button>,
+}
+
+impl EndScreenStage {
+    pub fn new(config: EndScreen) -> Self {
+        Self {
+            config,
+            time: 0.0,
+            buttons: Vec::new(),
+        }
+    }
+    fn fading(&self) -> bool {
+        self.time < self.config.fade.seconds
+    }
+    fn shortcut(&self, shared: &Shared, input: &Box<dyn InputTrait>) -> Option<EndButton> {
+        let has_restart = self.config.buttons.contains(&EndButton::Restart);
+        (has_restart && shared.is_pressed(Action::Restart, input)).then_some(EndButton::Restart)
+    }
+}
+
+impl Stage for EndScreenStage {
+    fn enter(&mut self, shared: &mut Shared, _input: &Box<dyn InputTrait>) {
+        if let Some(sound) = self.config.sound {
+            shared.audio.play(sound);
+        }
+    }
+
+    fn step(
+        &mut self,
+        shared: &mut Shared,
+        input: &Box<dyn InputTrait>,
+        dt: f32,
+    ) -> Option<StageOutcome> {
+        if shared.settings.reduced_motion {
+            self.time = self.config.fade.seconds;
+        }
+        if self.fading() {
+            self.time += dt;
+            return None;
+        }
+        if let Some(button) = self.shortcut(shared, input) {
+            return Some(button.outcome());
+        }
+        self.buttons.clear();
+        let mut anchor = Anchor::center_v(shared.screen_center());
+        for end_button in &self.config.buttons {
+            let mut button = shared.new_button(shared.text(end_button.text_key()), anchor, input);
+            if button.interact().is_clicked() {
+                return Some(end_button.outcome());
+            }
+            anchor = Anchor::below(button.rect(), Horizontal::Center, 0.0);
+            self.buttons.push(button);
+        }
+        None
+    }
+
+    fn render(&self, shared: &Shared) {
+        let config = &self.config;
+        clear_background(config.fade.color(self.time));
+        let anchor = Anchor::center(shared.screen.x * 0.5, shared.screen.y * 0.25);
+        let text_rect = TextRect::new(shared.text(config.title_key), anchor, FONT_SIZE * 5.0);
+        draw_rect(text_rect.rect(), config.fade.to);
+        text_rect.render_default(&config.title_style);
+        if !self.fading() {
+            if let Some(subtitle_key) = config.subtitle_key {
+                let anchor_subtext =
+                    Anchor::below(text_rect.rect(), Horizontal::Center, -2.0 * FONT_SIZE);
+                let subtext =
+                    TextRect::new(shared.text(subtitle_key), anchor_subtext, FONT_SIZE * 2.0);
+                subtext.render_default(&config.subtitle_style);
+            }
+            for button in &self.buttons {
+                render_button(button);
+            }
+        }
+    }
+
+    fn inspect(&self, _shared: &Shared, overlay: &mut DevOverlay) {
+        let fade = &self.config.fade;
+        overlay.line(format!("fade: {:.1}s of {:.1}s", self.time, fade.seconds));
+        for button in &self.buttons {
+            overlay.hitbox(Hitbox::Button, Shape::rect(button.rect()));
+        }
+    }
+}
@@ -1,98 +1,264 @@
-use crate::{
-    animate_pos_to, create_tooltip_anchor, new_button_grabbed, render_button, render_tooltip,
-    should_quit, GrabbedMouseInput, DIALOG_DELAY_SECONDS, STAGE_PRISON_DIALOGS,
-};
+use crate::achievements::Achievement;
+use crate::actions::Action;
+use crate::audio::SoundEffect;
+use crate::dev_overlay::{DevOverlay, FieldView, Hitbox};
+use crate::dialog::{render_dialog, DialogEvents, DialogRunner, DialogScript};
+use crate::geometry::Shape;
+use crate::stages::driver::{Shared, Stage, StageOutcome};
+use crate::virtual_cursor::{cursor_shape, render_cursor, VirtualCursor};
+use crate::{render_button, whoosh, GrabbedMouseInput};
 use juquad::draw::draw_rect_lines;
 use juquad::input::input_trait::InputTrait;
-use juquad::widgets::anchor::Anchor;
+use juquad::widgets::anchor::{Anchor, Horizontal};
 use juquad::widgets::button::Button;
-use macroquad::miniquad::date::now;
-use macroquad::prelude::{
-    clear_background, is_key_pressed, mouse_position, next_frame, screen_height, screen_width,
-    set_cursor_grab, vec2, KeyCode, Rect, Vec2, DARKPURPLE, LIGHTGRAY,
-};
+use juquad::widgets::{Interaction, Widget};
+use macroquad::color::Color;
+use macroquad::prelude::{clear_background, vec2, Rect, Vec2, DARKPURPLE, LIGHTGRAY};
 
-pub async fn stage_prison(mut button: Button) {
-    let dialogs = STAGE_PRISON_DIALOGS;
-    let mut dialog_index: usize;
+const BORDER_THICKNESS: f32 = 8.0;
+const FADE_PER_SECOND: f32 = 0.6;
 
-    let fake_mouse_origin = Vec2::from(mouse_position());
-    let input_grabbed: Box<dyn InputTrait> = Box::new(GrabbedMouseInput::new(fake_mouse_origin));
-    println!(
-        "mouse pos: {:?}, corrected: {:?}",
-        mouse_position(),
-        input_grabbed.mouse_position()
-    );
-    // set_cursor_grab(true);
-    println!(
-        "mouse pos after grab: {:?}, corrected: {:?}",
-        mouse_position(),
-        input_grabbed.mouse_position()
-    );
+enum Phase {
+    Imprisoned,
+    GivingUp { bg_color: Color },
+    GaveUp,
+}
+
+pub struct PrisonStage {
+    cursor: VirtualCursor,
+    stage_2_duration: f64,
+    dialog: DialogRunner,
+    /// The dialog reached the line that suggests giving up
+    give_up_offered: bool,
+    phase: Phase,
+    escape: Option<Button>,
+    give_up: Option<Button>,
+    restart: Option<Button>,
+    exit: Option<Button>,
+}
+
+impl Default for PrisonStage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    let stage_2_start_ts = now();
-    loop {
-        if should_quit() {
-            set_cursor_grab(false);
-            break;
+impl PrisonStage {
+    pub fn new() -> Self {
+        Self {
+            cursor: VirtualCursor::new(Vec2::ZERO, Rect::new(0.0, 0.0, 0.0, 0.0)),
+            stage_2_duration: 0.0,
+            dialog: DialogRunner::new(DialogScript::default()),
+            give_up_offered: false,
+            phase: Phase::Imprisoned,
+            escape: None,
+            give_up: None,
+            restart: None,
+            exit: None,
         }
-        if is_key_pressed(KeyCode::Space) {
+    }
+    fn screen_rect(screen: Vec2) -> Rect {
+        Rect::new(1.0, 1.0, screen.x - 2.0, screen.y - 2.0)
+    }
+    /// Where the button hides
+    fn home(screen: Vec2) -> Vec2 {
+        vec2(screen.x * 0.75, screen.y * 0.25)
+    }
+    /// The inner edge of the purple border
+    fn cursor_bounds(screen: Vec2) -> Rect {
+        let half = BORDER_THICKNESS * 0.5;
+        let rect = Self::screen_rect(screen);
+        Rect::new(
+            rect.x + half,
+            rect.y + half,
+            (rect.w - BORDER_THICKNESS).max(0.0),
+            (rect.h - BORDER_THICKNESS).max(0.0),
+        )
+    }
+}
+
+impl Stage for PrisonStage {
+    fn enter(&mut self, shared: &mut Shared, input: &Box<dyn InputTrait>) {
+        let mouse_pos = input.mouse_position();
+        self.cursor = VirtualCursor::new(mouse_pos, Self::cursor_bounds(shared.screen));
+        shared.cursor_grabbed = true;
+        self.stage_2_duration = 0.0;
+        self.dialog = shared.dialogs.runner("prison");
+    }
+
+    fn step(
+        &mut self,
+        shared: &mut Shared,
+        input: &Box<dyn InputTrait>,
+        dt: f32,
+    ) -> Option<StageOutcome> {
+        let mouse_pos = input.mouse_position();
+        if shared.is_pressed(Action::DebugDump, input) {
             println!(
-                "mouse pos: {:?}, corrected: {:?}",
-                mouse_position(),
-                input_grabbed.mouse_position()
+                "mouse pos: {:?}, virtual: {:?}",
+                mouse_pos,
+                self.cursor.pos()
             );
         }
-        let (sw, sh) = (screen_width(), screen_height());
-        let screen_rect = Rect::new(1.0, 1.0, sw - 2.0, sh - 2.0);
-        clear_background(LIGHTGRAY);
-        let stage_2_duration = now() - stage_2_start_ts;
-        let thickness = if stage_2_duration < 1.0 {
-            stage_2_duration * 40.0
-        } else if stage_2_duration < 2.0 {
-            (2.0 - stage_2_duration) * 32.0 + 8.0
-        } else {
-            8.0
-        } as f32;
-        draw_rect_lines(screen_rect, thickness, DARKPURPLE);
-        animate_pos_to(&mut button, vec2(sw * 0.75, sh * 0.25));
-        render_button(&button);
-        if stage_2_duration < DIALOG_DELAY_SECONDS {
-            dialog_index = 0;
-            let anchor = create_tooltip_anchor(sw, sh, button.rect());
-            render_tooltip(dialogs[dialog_index], anchor);
-        } else if stage_2_duration < DIALOG_DELAY_SECONDS * 1.25 {
-            // wait
-        } else {
-            dialog_index = 1;
-            let anchor = create_tooltip_anchor(sw, sh, button.rect());
-            render_tooltip(dialogs[dialog_index], anchor);
-            let give_up_anchor = Anchor::center_v(screen_rect.center());
-            let mut give_up = new_button_grabbed("Give up", give_up_anchor, &input_grabbed);
-            if give_up.interact().is_clicked() {
-                set_cursor_grab(false);
-                let mut bg_color = LIGHTGRAY;
-                loop {
-                    clear_background(bg_color);
-                    bg_color.r -= 0.01;
-                    bg_color.g -= 0.01;
-                    bg_color.b -= 0.01;
-                    if bg_color.r < 0.2 {
-                        break;
-                    }
-                    next_frame().await;
+        let give_up_anchor = Anchor::center_v(Self::screen_rect(shared.screen).center());
+        match &mut self.phase {
+            Phase::Imprisoned => {
+                self.stage_2_duration += dt as f64;
+                self.cursor.set_bounds(Self::cursor_bounds(shared.screen));
+                self.cursor.sensitivity = shared.settings.prison_sensitivity;
+                self.cursor.accumulate(mouse_pos);
+                let input_grabbed: Box<dyn InputTrait> =
+                    Box::new(GrabbedMouseInput::new(input, self.cursor.pos()));
+
+                // the only way out is catching the button, which hides in its corner
+                let mouse_pos = self.cursor.pos();
+                let home = Self::home(shared.screen);
+                let time = self.stage_2_duration as f32;
+                let displacement = shared.push_button("prison", mouse_pos, home, time, dt);
+                whoosh(&mut shared.audio, displacement, dt);
+                let button = &mut shared.button;
+                let new_pos = button.rect().center() + displacement;
+                button.reanchor(Anchor::center_v(new_pos));
+                let mut escape = shared.new_button(
+                    shared.text(shared.button_key),
+                    Anchor::center_v(new_pos),
+                    &input_grabbed,
+                );
+                let interaction = escape.interact();
+                if interaction.is_clicked() {
+                    shared.save.update(|data| data.escapes += 1);
+                    return Some(StageOutcome::Escaped);
+                }
+                self.escape = Some(escape);
+                let events = DialogEvents {
+                    hovered: interaction != Interaction::None,
+                    clicked: false,
+                };
+                if self.dialog.step(events, dt) {
+                    shared.audio.play(SoundEffect::Blip);
                 }
-                loop {
-                    let mut restart = new_button_grabbed("Restart", give_up_anchor, &input_grabbed);
-                    if restart.interact().is_clicked() {
-                        return;
+                if self.dialog.current_id() == Some("give_up") {
+                    self.give_up_offered = true;
+                }
+
+                if !self.give_up_offered {
+                    self.give_up = None;
+                } else {
+                    let mut give_up =
+                        shared.new_button(shared.text("give_up"), give_up_anchor, &input_grabbed);
+                    if give_up.interact().is_clicked() {
+                        shared.cursor_grabbed = false;
+                        self.phase = Phase::GivingUp {
+                            bg_color: LIGHTGRAY,
+                        };
                     }
-                    render_button(&restart);
-                    next_frame().await;
+                    self.give_up = Some(give_up);
                 }
             }
-            render_button(&give_up);
+            Phase::GivingUp { bg_color } => {
+                let fade = FADE_PER_SECOND * dt;
+                bg_color.r -= fade;
+                bg_color.g -= fade;
+                bg_color.b -= fade;
+                if bg_color.r < 0.2 || shared.settings.reduced_motion {
+                    self.phase = Phase::GaveUp;
+                }
+            }
+            Phase::GaveUp => {
+                let mut restart = shared.new_button(shared.text("restart"), give_up_anchor, input);
+                if restart.interact().is_clicked() {
+                    return Some(StageOutcome::Restart);
+                }
+                let exit_anchor = Anchor::below(restart.rect(), Horizontal::Center, 0.0);
+                let mut exit = shared.new_button(shared.text("exit"), exit_anchor, input);
+                if exit.interact().is_clicked() {
+                    shared.save.update(|data| data.gave_up += 1);
+                    shared.achieve(Achievement::Surrender);
+                    return Some(StageOutcome::GaveUp);
+                }
+                self.restart = Some(restart);
+                self.exit = Some(exit);
+            }
         }
-        next_frame().await;
+        None
+    }
+
+    fn render(&self, shared: &Shared) {
+        match &self.phase {
+            Phase::Imprisoned => {
+                clear_background(LIGHTGRAY);
+                let stage_2_duration = self.stage_2_duration;
+                let thickness = if shared.settings.reduced_motion {
+                    BORDER_THICKNESS as f64
+                } else if stage_2_duration < 1.0 {
+                    stage_2_duration * 40.0
+                } else if stage_2_duration < 2.0 {
+                    (2.0 - stage_2_duration) * 32.0 + BORDER_THICKNESS as f64
+                } else {
+                    BORDER_THICKNESS as f64
+                } as f32;
+                draw_rect_lines(Self::screen_rect(shared.screen), thickness, DARKPURPLE);
+                if let Some(escape) = &self.escape {
+                    render_button(escape);
+                }
+                render_dialog(
+                    &self.dialog,
+                    &shared.strings,
+                    shared.screen,
+                    shared.button.rect(),
+                );
+                if let Some(give_up) = &self.give_up {
+                    render_button(give_up);
+                }
+                render_cursor(self.cursor.pos());
+            }
+            Phase::GivingUp { bg_color } => {
+                clear_background(*bg_color);
+            }
+            Phase::GaveUp => {
+                if let Some(restart) = &self.restart {
+                    render_button(restart);
+                }
+                if let Some(exit) = &self.exit {
+                    render_button(exit);
+                }
+            }
+        }
+    }
+
+    fn inspect(&self, shared: &Shared, overlay: &mut DevOverlay) {
+        let phase = match self.phase {
+            Phase::Imprisoned => "imprisoned",
+            Phase::GivingUp { .. } => "giving up",
+            Phase::GaveUp => "gave up",
+        };
+        overlay.line(format!("phase: {}", phase));
+        if let Phase::Imprisoned = self.phase {
+            let cursor = self.cursor.pos();
+            overlay.field(FieldView {
+                stage: "prison",
+                mice: vec![cursor],
+                home: Self::home(shared.screen),
+                time: self.stage_2_duration as f32,
+            });
+            overlay.hitbox(Hitbox::Button, Shape::rect(shared.button.rect()));
+            overlay.hitbox(Hitbox::Mouse, cursor_shape(cursor));
+            overlay.line(format!("virtual cursor: {}", cursor));
+            overlay.line(format!("imprisoned: {:.1}s", self.stage_2_duration));
+            overlay.dialog(&self.dialog);
+        }
+    }
+
+    fn resume(&mut self, _shared: &mut Shared, _input: &Box<dyn InputTrait>) {
+        self.cursor.reset_reference();
+    }
+
+    /// Once the player gave up there is nothing left to keep them in
+    fn lets_quit(&self) -> bool {
+        matches!(self.phase, Phase::GaveUp)
+    }
+
+    fn exit(&mut self, shared: &mut Shared) {
+        shared.cursor_grabbed = false;
     }
 }
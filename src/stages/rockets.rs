@@ -1,119 +1,395 @@
-use crate::stages::game_over::{stage_game_over, AfterGameOver};
-use crate::stages::game_won::{stage_game_won, AfterGameWon};
-use crate::{compute_force_towards, render_button, should_quit};
-use juquad::draw::draw_rect_lines;
-use juquad::widgets::anchor::Anchor;
-use juquad::widgets::button::Button;
+use crate::achievements::{Achievement, SURVIVOR_SECONDS};
+use crate::actions::Action;
+use crate::audio::SoundEffect;
+use crate::dev_overlay::{DevOverlay, FieldView, Hitbox};
+use crate::geometry::{draw_shape_lines, swept_overlaps, Shape};
+use crate::launcher::{place, LaunchRules, Launcher, Telegraph};
+use crate::particles::{Particles, EXHAUST, EXPLOSION, SPARKS};
+use crate::stages::driver::{Shared, Stage, StageOutcome};
+use crate::steering::RocketKind;
+use crate::virtual_cursor::cursor_shape;
+use crate::waves::{Wave, Waves};
+use crate::{render_button, whoosh, FONT_SIZE, STYLE};
+use juquad::input::input_trait::InputTrait;
+use juquad::widgets::anchor::{Anchor, Horizontal, Vertical};
+use juquad::widgets::text::TextRect;
 use juquad::widgets::Widget;
-use macroquad::color::{LIGHTGRAY, WHITE};
-use macroquad::input::KeyCode;
-use macroquad::prelude::{clear_background, draw_triangle, draw_triangle_lines, is_key_pressed, mouse_position, next_frame, screen_height, screen_width, vec2, Rect, Vec2, RED, SKYBLUE};
+use macroquad::color::{DARKGRAY, LIGHTGRAY};
+use macroquad::prelude::{
+    clear_background, draw_circle_lines, draw_line, draw_rectangle, draw_triangle,
+    draw_triangle_lines, vec2, Rect, Vec2, SKYBLUE,
+};
 
 #[derive(PartialEq)]
 pub struct Rocket {
+    kind: RocketKind,
     pos: Vec2,
+    /// Where it steers to, normalized. It flies along `dir`, which can swing around it.
+    heading: Vec2,
     dir: Vec2,
+    /// In pixels per second
+    speed: f32,
+    /// Multiplies how fast it steers
+    turn: f32,
+    /// Seconds left before burning out
+    fuel: f32,
+    /// How much it moved in the last step
+    motion: Vec2,
+    /// Seconds since launch
+    age: f32,
 }
 
-const ROCKET_SPEED: f32 = 10.0;
+impl Rocket {
+    fn new(kind: RocketKind, pos: Vec2, heading: Vec2, speed: f32, turn: f32, fuel: f32) -> Self {
+        Self {
+            kind,
+            pos,
+            heading,
+            dir: heading * speed,
+            speed,
+            turn,
+            fuel,
+            motion: Vec2::ZERO,
+            age: 0.0,
+        }
+    }
+
+    /// The rockets it breaks into, if it's time
+    fn split(&self) -> Option<Vec<Rocket>> {
+        let params = self.kind.params();
+        let split = params.split.filter(|split| self.age >= split.after)?;
+        let speed = self.speed * split.child.params().speed / params.speed;
+        let children = split.headings(self.heading).into_iter().map(|heading| {
+            Rocket::new(split.child, self.pos, heading, speed, self.turn, self.fuel)
+        });
+        Some(children.collect())
+    }
+}
+
+/// In pixels per second
+const ROCKET_SPEED: f32 = 600.0;
 const ROCKET_RENDER_WIDTH: f32 = 15.0;
 const ROCKET_RENDER_LENGTH: f32 = 40.0;
-const MOUSE_SIZE: Vec2 = vec2(15.0, 20.0);
+/// How long the button explodes before the stage ends
+const EXPLOSION_SECONDS: f32 = 1.2;
+const PARTICLE_CAPACITY: usize = 512;
+const TURRET_SIZE: f32 = 24.0;
+/// Pixels of the aim line of a launch about to happen
+const TELEGRAPH_LENGTH: f32 = 120.0;
+
+pub struct RocketsStage {
+    rockets: Vec<Rocket>,
+    waves: Waves,
+    launcher: Launcher,
+    mouse_pos: Option<Vec2>,
+    /// Since the stage started
+    survived: f32,
+    /// Whether this run already survived long enough for the achievement
+    survivor: bool,
+    particles: Particles,
+    /// Seconds since the rocket hit the button
+    exploding: Option<f32>,
+}
+
+impl Default for RocketsStage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RocketsStage {
+    pub fn new() -> Self {
+        Self {
+            rockets: Vec::new(),
+            waves: Waves::default(),
+            launcher: Launcher::new(LaunchRules::embedded()),
+            mouse_pos: None,
+            survived: 0.0,
+            survivor: false,
+            particles: Particles::new(PARTICLE_CAPACITY, 1),
+            exploding: None,
+        }
+    }
+}
+
+impl Stage for RocketsStage {
+    fn enter(&mut self, shared: &mut Shared, input: &Box<dyn InputTrait>) {
+        let anchor = Anchor::center_v(shared.screen_center());
+        shared.button = shared.new_button(shared.text(shared.button_key), anchor, input);
+        self.rockets.clear();
+        self.waves = Waves::new(shared.waves.clone());
+        self.launcher = Launcher::new(shared.launch_rules.clone());
+        self.survived = 0.0;
+        self.survivor = false;
+        self.mouse_pos = None;
+        self.particles.clear();
+        self.exploding = None;
+    }
 
-pub async fn stage_rockets(mut button: Button) {
-    let mut rocket: Option<Rocket> = None;
-    loop {
-        if should_quit() {
-            break;
+    fn step(
+        &mut self,
+        shared: &mut Shared,
+        input: &Box<dyn InputTrait>,
+        dt: f32,
+    ) -> Option<StageOutcome> {
+        let mouse_pos = input.mouse_position();
+        if shared.is_pressed(Action::DebugDump, input) {
+            println!("mouse pos: {:?}", mouse_pos);
         }
-        if is_key_pressed(KeyCode::Space) {
-            println!("mouse pos: {:?}", mouse_position());
+        if shared.is_pressed(Action::Restart, input) {
+            return Some(StageOutcome::Restart);
         }
-        let (sw, sh) = (screen_width(), screen_height());
-        let screen_center = vec2(sw * 0.5, sh * 0.5);
-        if is_key_pressed(KeyCode::R) {
-            button.reanchor(Anchor::center_v(screen_center));
-            rocket = None;
+        self.particles.step(dt);
+        if let Some(time) = self.exploding.as_mut() {
+            *time += dt;
+            return (*time >= EXPLOSION_SECONDS).then_some(StageOutcome::Won);
         }
+        let home = shared.screen_center();
+        let displacement = shared.push_button("rockets", mouse_pos, home, self.survived, dt);
+        whoosh(&mut shared.audio, displacement, dt);
+        let button = &mut shared.button;
 
-        let mouse_pos = Vec2::from(mouse_position());
         let button_center = button.rect().center();
-        let displacement = compute_force_towards(mouse_pos, button_center, screen_center);
         let new_pos = button_center + displacement;
         button.reanchor(Anchor::center_v(new_pos));
 
-        if let Some(rocket) = rocket.as_mut() {
-            rocket.pos += rocket.dir;
-            rocket.dir += (mouse_pos - rocket.pos).normalize_or_zero() * 3.0;
-            rocket.dir += -(button_center - rocket.pos).normalize_or_zero() * 2.3;
-            rocket.dir = rocket.dir.normalize_or_zero() * ROCKET_SPEED;
-        } else /*if button_center */{
-            rocket = Some(Rocket {
-                pos: button_center - vec2(0.0, button.rect().h),
-                dir: vec2(0.0, -1.0),
-            });
+        let reduced_motion = shared.settings.reduced_motion;
+        let mut children = Vec::new();
+        for rocket in &mut self.rockets {
+            let steering = rocket.kind.params().steering;
+            rocket.motion = rocket.dir * dt;
+            rocket.pos += rocket.motion;
+            rocket.age += dt;
+            rocket.heading = steering.steer(
+                rocket.heading,
+                rocket.pos,
+                mouse_pos,
+                button_center,
+                rocket.turn,
+                dt,
+            );
+            rocket.dir = steering.direction(rocket.heading, rocket.age) * rocket.speed;
+            rocket.fuel -= dt;
+            if let Some(split) = rocket.split() {
+                children.extend(split);
+                rocket.fuel = 0.0;
+            }
+            if !reduced_motion {
+                let length = ROCKET_RENDER_LENGTH * rocket.kind.params().look.size;
+                let tail = rocket.pos - rocket.dir.normalize_or_zero() * length * 0.6;
+                self.particles.emit(&EXHAUST, tail, -rocket.dir);
+            }
         }
-
-        let mouse_rect = Rect::new(
-            mouse_pos.x - 2.0,
-            mouse_pos.y - 2.0,
-            MOUSE_SIZE.x,
-            MOUSE_SIZE.y,
-        );
-        if collide_rocket(&rocket, mouse_rect) {
-            match stage_game_over(sw, sh, screen_center).await {
-                AfterGameOver::RestartStage => {}
-                AfterGameOver::Quit => {
-                    return;
-                }
+        if !reduced_motion {
+            for rocket in self.rockets.iter().filter(|rocket| rocket.fuel <= 0.0) {
+                self.particles.emit(&SPARKS, rocket.pos, rocket.dir);
             }
         }
-        if collide_rocket(&rocket, button.rect()) {
-            match stage_game_won(sw, sh, screen_center).await {
-                AfterGameWon::RestartStage => {}
-                AfterGameWon::Quit => {
-                    return;
-                }
+        self.rockets.retain(|rocket| rocket.fuel > 0.0);
+        self.rockets.extend(children);
+        if !self.rockets.is_empty() {
+            shared.audio.play(SoundEffect::Thrust);
+        }
+
+        let flying = self.rockets.len() + self.launcher.pending();
+        if self.waves.step(dt, flying) {
+            let wave = self.waves.current();
+            let kind = wave.kind(self.waves.launched() - 1);
+            self.launcher.request(kind, wave.number);
+        }
+        if let Some(launch) = self.launcher.step(dt, self.rockets.len()) {
+            let wave = self.waves.current();
+            let params = launch.kind.params();
+            let speed = ROCKET_SPEED
+                * wave.speed
+                * params.speed
+                * shared.settings.difficulty.speed_factor();
+            let (pos, heading) = launch_from(&launch, button.rect(), shared.screen, mouse_pos);
+            let fuel = wave.fuel * params.fuel;
+            let rocket = Rocket::new(launch.kind, pos, heading, speed, wave.turn, fuel);
+            self.rockets.push(rocket);
+            shared.audio.play(SoundEffect::Whoosh);
+        }
+        self.survived += dt;
+
+        let mouse_motion = mouse_pos - self.mouse_pos.unwrap_or(mouse_pos);
+        self.mouse_pos = Some(mouse_pos);
+        if collide_rockets(&self.rockets, &cursor_shape(mouse_pos), mouse_motion) {
+            shared.save.update(|data| data.deaths += 1);
+            return Some(StageOutcome::Lost);
+        }
+        let button_rect = button.rect();
+        if collide_rockets(&self.rockets, &Shape::rect(button_rect), displacement) {
+            shared.save.update(|data| data.buttons_destroyed += 1);
+            shared.achieve(Achievement::Demolition);
+            shared.audio.play(SoundEffect::Explosion);
+            if shared.settings.reduced_motion {
+                return Some(StageOutcome::Won);
             }
+            self.particles
+                .emit(&EXPLOSION, button_rect.center(), Vec2::X);
+            self.rockets.clear();
+            self.exploding = Some(0.0);
+            return None;
+        }
+        if !self.survivor && self.survived >= SURVIVOR_SECONDS {
+            self.survivor = true;
+            shared.achieve(Achievement::Survivor);
         }
+        None
+    }
 
+    fn render(&self, shared: &Shared) {
         clear_background(LIGHTGRAY);
-        draw_rect_lines(mouse_rect, 4.0, SKYBLUE);
-        render_button(&button);
-        render_rocket(&rocket);
-        next_frame().await
+        if let Some(mouse_pos) = self.mouse_pos {
+            draw_shape_lines(&cursor_shape(mouse_pos), 4.0, SKYBLUE);
+        }
+        if self.exploding.is_none() {
+            render_button(&shared.button);
+        }
+        let rules = self.launcher.rules();
+        for turret in rules.turrets(self.waves.current().number) {
+            let center = turret * shared.screen;
+            let corner = center - Vec2::splat(TURRET_SIZE * 0.5);
+            draw_rectangle(corner.x, corner.y, TURRET_SIZE, TURRET_SIZE, DARKGRAY);
+        }
+        if let (Some(telegraph), Some(mouse_pos)) = (self.launcher.telegraph(), self.mouse_pos) {
+            render_telegraph(telegraph, shared, mouse_pos);
+        }
+        self.particles.render();
+        for rocket in &self.rockets {
+            render_rocket(rocket);
+        }
+        render_wave_hud(shared, self.waves.current());
     }
-}
 
-fn render_rocket(rocket: &Option<Rocket>) {
-    if let Some(rocket) = rocket {
-        //let render_radius = ROCKET_RENDER_WIDTH;
-        // draw_circle(rocket.pos.x, rocket.pos.y, render_radius, RED);
-        let dir_norm = rocket.dir.clone().normalize_or_zero();
-        let to_front = dir_norm * ROCKET_RENDER_LENGTH * 0.5;
-        let to_left = vec2(dir_norm.y, -dir_norm.x) * ROCKET_RENDER_WIDTH * 0.5;
-        let front = rocket.pos + to_front;
-        let back = rocket.pos - to_front;
-        let left = rocket.pos + to_left;
-        let right = rocket.pos - to_left;
-        let left_wing = rocket.pos - to_front*1.2 + to_left;
-        let right_wing = rocket.pos - to_front*1.2 - to_left;
-        //draw_triangle(left_wing, right_wing, rocket.pos, WHITE);
-        draw_triangle(left_wing, back, rocket.pos, WHITE);
-        draw_triangle_lines(left_wing, back, rocket.pos, 2.0, RED);
-        draw_triangle(right_wing, rocket.pos, back, WHITE);
-        draw_triangle_lines(right_wing, rocket.pos, back, 2.0, RED);
-        draw_triangle(front, left, right, RED);
-        draw_triangle(back, right, left, RED);
+    fn inspect(&self, shared: &Shared, overlay: &mut DevOverlay) {
+        if let Some(mouse_pos) = self.mouse_pos {
+            overlay.field(FieldView {
+                stage: "rockets",
+                mice: vec![mouse_pos],
+                home: shared.screen_center(),
+                time: self.survived,
+            });
+            overlay.hitbox(Hitbox::Mouse, cursor_shape(mouse_pos));
+        }
+        if self.exploding.is_none() {
+            overlay.hitbox(Hitbox::Button, Shape::rect(shared.button.rect()));
+        }
+        for rocket in &self.rockets {
+            let hitbox = if rocket.kind.params().harmful {
+                Hitbox::Rocket
+            } else {
+                Hitbox::Decoy
+            };
+            overlay.hitbox(hitbox, rocket_shape(rocket));
+        }
+        let wave = self.waves.current();
+        overlay.line(format!(
+            "wave {}: {}/{} launched{}",
+            wave.number,
+            self.waves.launched(),
+            wave.rockets,
+            if self.waves.in_break() {
+                ", in break"
+            } else {
+                ""
+            }
+        ));
+        overlay.line(format!(
+            "rockets: {} flying, {} queued",
+            self.rockets.len(),
+            self.launcher.pending()
+        ));
+        if let Some(telegraph) = self.launcher.telegraph() {
+            overlay.line(format!(
+                "winding up {:?}: {:.0}%",
+                telegraph.kind,
+                telegraph.progress() * 100.0
+            ));
+        }
+        overlay.line(format!("survived: {:.1}s", self.survived));
+        if let Some(time) = self.exploding {
+            overlay.line(format!("exploding: {:.1}s", time));
+        }
     }
 }
 
-fn collide_rocket(rocket: &Option<Rocket>, target: Rect) -> bool {
-    if let Some(rocket) = rocket {
-        let dir_norm = rocket.dir.clone().normalize_or_zero();
-        let front = rocket.pos + dir_norm * ROCKET_RENDER_LENGTH * 0.5;
-        target.contains(front)
+fn render_wave_hud(shared: &Shared, wave: &Wave) {
+    let text = format!("{} {}", shared.text("wave"), wave.number);
+    let anchor = Anchor::new(Horizontal::Left, Vertical::Top, FONT_SIZE, FONT_SIZE);
+    let text_rect = TextRect::new(&text, anchor, FONT_SIZE * 1.5);
+    text_rect.render_default(&STYLE.at_rest);
+}
+
+/// A growing aim line from where the rocket will appear, and a ring closing in on that spot
+fn render_telegraph(telegraph: &Telegraph, shared: &Shared, mouse_pos: Vec2) {
+    let (pos, heading) = launch_from(telegraph, shared.button.rect(), shared.screen, mouse_pos);
+    let progress = telegraph.progress();
+    let mut color = telegraph.kind.params().look.body;
+    color.a = 0.3 + 0.7 * progress;
+    let end = pos + heading * TELEGRAPH_LENGTH * progress;
+    draw_line(pos.x, pos.y, end.x, end.y, 3.0, color);
+    let radius = ROCKET_RENDER_LENGTH * (1.5 - progress);
+    draw_circle_lines(pos.x, pos.y, radius, 2.0, color);
+}
+
+fn render_rocket(rocket: &Rocket) {
+    //let render_radius = ROCKET_RENDER_WIDTH;
+    // draw_circle(rocket.pos.x, rocket.pos.y, render_radius, RED);
+    let look = rocket.kind.params().look;
+    let dir_norm = rocket.dir.normalize_or_zero();
+    let to_front = dir_norm * ROCKET_RENDER_LENGTH * look.size * 0.5;
+    let to_left = vec2(dir_norm.y, -dir_norm.x) * ROCKET_RENDER_WIDTH * look.size * 0.5;
+    let front = rocket.pos + to_front;
+    let back = rocket.pos - to_front;
+    let left = rocket.pos + to_left;
+    let right = rocket.pos - to_left;
+    let left_wing = rocket.pos - to_front * 1.2 + to_left;
+    let right_wing = rocket.pos - to_front * 1.2 - to_left;
+    //draw_triangle(left_wing, right_wing, rocket.pos, WHITE);
+    draw_triangle(left_wing, back, rocket.pos, look.wings);
+    draw_triangle_lines(left_wing, back, rocket.pos, 2.0, look.body);
+    draw_triangle(right_wing, rocket.pos, back, look.wings);
+    draw_triangle_lines(right_wing, rocket.pos, back, 2.0, look.body);
+    draw_triangle(front, left, right, look.body);
+    draw_triangle(back, right, left, look.body);
+}
+
+/// The outline of what `render_rocket` draws
+fn rocket_shape(rocket: &Rocket) -> Shape {
+    let size = rocket.kind.params().look.size;
+    let dir_norm = rocket.dir.normalize_or_zero();
+    let to_front = dir_norm * ROCKET_RENDER_LENGTH * size * 0.5;
+    let to_left = vec2(dir_norm.y, -dir_norm.x) * ROCKET_RENDER_WIDTH * size * 0.5;
+    Shape::Polygon(vec![
+        rocket.pos + to_front,
+        rocket.pos + to_left,
+        rocket.pos - to_front * 1.2 + to_left,
+        rocket.pos - to_front * 1.2 - to_left,
+        rocket.pos - to_left,
+    ])
+}
+
+/// Where the rocket of a launch appears, and its heading
+fn launch_from(launch: &Telegraph, button: Rect, screen: Vec2, mouse_pos: Vec2) -> (Vec2, Vec2) {
+    let params = launch.kind.params();
+    let clearance = ROCKET_RENDER_LENGTH * params.look.size;
+    let (pos, dir) = place(launch.point, launch.turn, button, screen, clearance);
+    if params.aims_at_launch {
+        (pos, (mouse_pos - pos).try_normalize().unwrap_or(dir))
     } else {
-        false
+        (pos, dir)
     }
 }
+
+/// Whether any harmful rocket touched the target at some point of the last step, while both
+/// moved
+fn collide_rockets(rockets: &[Rocket], target: &Shape, target_motion: Vec2) -> bool {
+    let target_start = target.translated(-target_motion);
+    let mut harmful = rockets.iter().filter(|rocket| rocket.kind.params().harmful);
+    harmful.any(|rocket| {
+        let start = rocket_shape(rocket).translated(-rocket.motion);
+        swept_overlaps(&start, rocket.motion - target_motion, &target_start)
+    })
+}
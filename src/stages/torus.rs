@@ -1,145 +1,112 @@
+use crate::achievements::Achievement;
+use crate::actions::Action;
+use crate::audio::SoundEffect;
+use crate::dev_overlay::{DevOverlay, FieldView, Hitbox};
+use crate::dialog::{render_dialog, DialogEvents, DialogRunner, DialogScript};
+use crate::geometry::Shape;
+use crate::particles::{Particles, SPARKS};
+use crate::stages::driver::{Shared, Stage, StageOutcome};
+use crate::torus_space::{Topology, TorusSpace};
+use crate::virtual_cursor::cursor_shape;
 use crate::{
-    compute_force, create_tooltip_anchor, draw_halo, new_button, render_button, render_tooltip,
-    should_quit, with_alpha, DIALOG_DELAY_SECONDS, FONT_SIZE, FORCE_RANGE_PIXELS,
-    STAGE_TORUS_DIALOGS, STAGE_TORUS_ENABLED, STYLE,
+    draw_halo, render_button, whoosh, with_alpha, FORCE_RANGE_PIXELS, STAGE_TORUS_ENABLED,
 };
-use juquad::draw::draw_rect;
-use juquad::widgets::anchor::{Anchor, Horizontal, Layout, Vertical};
-use juquad::widgets::anchorer::Anchorer;
+use juquad::input::input_trait::InputTrait;
+use juquad::widgets::anchor::Anchor;
 use juquad::widgets::button::Button;
 use juquad::widgets::{Interaction, Widget};
-use macroquad::color::{Color, DARKGREEN, GRAY, LIGHTGRAY};
-use macroquad::input::{is_key_pressed, mouse_position, KeyCode};
-use macroquad::math::{vec2, Rect, Vec2};
-use macroquad::miniquad::date::now;
-use macroquad::prelude::{clear_background, draw_line, next_frame, screen_height, screen_width};
+use macroquad::color::{GRAY, LIGHTGRAY};
+use macroquad::math::Vec2;
+use macroquad::prelude::clear_background;
 
-pub async fn stage_torus() -> Button {
-    let (sw, sh) = (screen_width(), screen_height());
-    let anchor = Anchor::center(sw * 0.5, sh * 0.5);
-    let mut text = "Start";
-    let mut dialog_index = 0;
-    let mut start_dialogue = None;
-    let mut button = new_button(text, anchor);
-    let layout = Layout::Vertical {
-        direction: Vertical::Bottom,
-        alignment: Horizontal::Left,
-    };
-    let debug = false;
-    let debug_field = false;
-    let mut move_button = true;
+const PARTICLE_CAPACITY: usize = 128;
 
-    let dialogs = STAGE_TORUS_DIALOGS;
-    loop {
-        if !STAGE_TORUS_ENABLED {
-            break;
+pub struct TorusStage {
+    topology: Topology,
+    dialog: DialogRunner,
+    move_button: bool,
+    toggle_move: Option<Button>,
+    mouse_pos: Vec2,
+    button_center: Vec2,
+    new_pos: Vec2,
+    mouse_diff: f32,
+    text_key: &'static str,
+    ghosts: Vec<Button>,
+    /// Sparks where the button wraps across an edge
+    particles: Particles,
+    /// Seconds since the stage started
+    elapsed: f32,
+}
+
+impl TorusStage {
+    pub fn new(topology: Topology) -> Self {
+        Self {
+            topology,
+            dialog: DialogRunner::new(DialogScript::default()),
+            move_button: true,
+            toggle_move: None,
+            mouse_pos: Vec2::ZERO,
+            button_center: Vec2::ZERO,
+            new_pos: Vec2::ZERO,
+            mouse_diff: 0.0,
+            text_key: "start",
+            ghosts: Vec::new(),
+            particles: Particles::new(PARTICLE_CAPACITY, 2),
+            elapsed: 0.0,
         }
-        if should_quit() {
-            break;
+    }
+}
+
+impl Stage for TorusStage {
+    fn enter(&mut self, shared: &mut Shared, input: &Box<dyn InputTrait>) {
+        let anchor = Anchor::center_v(shared.screen_center());
+        shared.button = shared.new_button(shared.text(self.text_key), anchor, input);
+        shared.button_key = self.text_key;
+        self.dialog = shared.dialogs.runner(self.topology.name());
+        self.elapsed = 0.0;
+    }
+
+    fn step(
+        &mut self,
+        shared: &mut Shared,
+        input: &Box<dyn InputTrait>,
+        dt: f32,
+    ) -> Option<StageOutcome> {
+        if !STAGE_TORUS_ENABLED {
+            return Some(StageOutcome::Next);
         }
-        if is_key_pressed(KeyCode::Space) {
-            println!("mouse pos: {:?}", mouse_position());
+        let mouse_pos = input.mouse_position();
+        if shared.is_pressed(Action::DebugDump, input) {
+            println!("mouse pos: {:?}", mouse_pos);
         }
-        clear_background(LIGHTGRAY);
-        if debug {
-            let mut toggle_move =
-                new_button("toggle move", Anchor::top_center(sw * 0.5, sh * 0.25));
+        let (sw, sh) = (shared.screen.x, shared.screen.y);
+        if !shared.dev_overlay {
+            self.toggle_move = None;
+        } else {
+            let anchor = Anchor::top_center(sw * 0.5, sh * 0.25);
+            let mut toggle_move = shared.new_button("toggle move", anchor, input);
             if toggle_move.interact().is_clicked() {
-                move_button = !move_button;
+                self.move_button = !self.move_button;
             }
-            render_button(&toggle_move);
-        }
-        let mouse_pos = Vec2::from(mouse_position());
-        let button_center = button.rect().center();
-        let n_x = 50;
-        let n_y = 50;
-        if debug_field {
-            let mut tooltip = None;
-            for i_x in 0..n_x {
-                for i_y in 0..n_y {
-                    let pos = vec2(i_x as f32 * sw / n_x as f32, i_y as f32 * sh / n_y as f32);
-                    let displacement = compute_force(pos, button_center);
-                    let force = displacement.length();
-                    let pad = 10.0;
-                    let max = 10000.0;
-                    let normalized = force.min(max).log10() / max.log10();
-                    let color = Color::new(normalized, normalized, normalized, 1.0);
-                    let rect = Rect::new(pos.x - pad, pos.y - pad, pad, pad);
-                    draw_rect(rect, color);
-                    if rect.contains(mouse_pos) {
-                        tooltip = Some(force);
-                    }
-                }
-            }
-            if let Some(force) = tooltip {
-                let anchor = Anchor::bottom_left_v(mouse_pos);
-                render_tooltip(&format!("force: {}", force), anchor);
-            }
-        }
-        let displacement = compute_force(mouse_pos, button_center);
+            self.toggle_move = Some(toggle_move);
+        }
+        self.mouse_pos = mouse_pos;
+        let space = TorusSpace::with_topology(shared.screen, self.topology);
+        let button_center = shared.button.rect().center();
+        self.elapsed += dt;
+        let displacement = wrapped_force(shared, &space, mouse_pos, self.elapsed, dt);
+        whoosh(&mut shared.audio, displacement, dt);
+        let button = &mut shared.button;
         let new_pos = button_center + displacement;
 
-        if move_button {
+        if self.move_button {
             button.reanchor(Anchor::center_v(new_pos));
+            *button.rect_mut() = space.confine(button.rect());
         }
-        let diff = (mouse_pos - button_center).length();
-        let alpha =
-            ((2.0 * FORCE_RANGE_PIXELS - diff) / (2.0 * FORCE_RANGE_PIXELS) / 3.0).clamp(0.0, 0.5);
-        // 2*RANGE => 0, 0.5*range => 1
-        let halo_color = with_alpha(GRAY, alpha);
-        draw_halo(new_pos.x, new_pos.y, diff, halo_color);
-        let size = button.rect().size();
-        let mut extra_buttons = Vec::new();
-        if button.rect().x < 0.0 {
-            let extra = Anchor::center_v(new_pos + vec2(sw, 0.0)).get_rect(size);
-            extra_buttons.push(extra);
-        }
-        if button.rect().y < 0.0 {
-            let extra = Anchor::center_v(new_pos + vec2(0.0, sh)).get_rect(size);
-            extra_buttons.push(extra);
-        }
-        if button.rect().right() > sw {
-            let extra = Anchor::center_v(new_pos + vec2(-sw, 0.0)).get_rect(size);
-            extra_buttons.push(extra);
-        }
-        if button.rect().bottom() > sh {
-            let extra = Anchor::center_v(new_pos + vec2(0.0, -sh)).get_rect(size);
-            extra_buttons.push(extra);
-        }
-        if button.rect().x < 0.0 && button.rect().y < 0.0 {
-            let extra = Anchor::center_v(new_pos + vec2(sw, sh)).get_rect(size);
-            extra_buttons.push(extra);
-        }
-        if button.rect().y < 0.0 && button.rect().right() > sw {
-            let extra = Anchor::center_v(new_pos + vec2(-sw, sh)).get_rect(size);
-            extra_buttons.push(extra);
-        }
-        if button.rect().right() > sw && button.rect().bottom() > sh {
-            let extra = Anchor::center_v(new_pos + vec2(-sw, -sh)).get_rect(size);
-            extra_buttons.push(extra);
-        }
-        if button.rect().bottom() > sh && button.rect().x < 0.0 {
-            let extra = Anchor::center_v(new_pos + vec2(sw, -sh)).get_rect(size);
-            extra_buttons.push(extra);
-        }
-        if debug {
-            let mut anchorer = Anchorer::new_pos(layout, vec2(0.0, 0.0), 0.0);
-
-            for t in [
-                // &format!("diff: {}", diff),
-                // &format!("diff.abs(): {}", diff.abs()),
-                // &format!("complementary: {}", complementary),
-                // &format!("clamped: {}", clamped),
-                // &format!("magnitude: {}", magnitude),
-                // &format!("force: {}", force),
-                // &format!("displacement: {}", displacement),
-                &format!("button center: {}", button_center),
-            ] {
-                let text_rect = anchorer.new_text(t, FONT_SIZE);
-                draw_rect(text_rect.rect(), LIGHTGRAY);
-                text_rect.render_default(&STYLE.at_rest);
-            }
-        }
+        self.button_center = button_center;
+        self.new_pos = new_pos;
+        self.mouse_diff = (mouse_pos - button_center).length();
+        let extra_buttons = space.wrapped_copies(button.rect());
 
         // check if any alternative buttons have interactions
         let original = button.rect();
@@ -151,6 +118,7 @@ pub async fn stage_torus() -> Button {
             *button.rect_mut() = *extra;
             interaction = button.interact();
         }
+        let through_ghost = interaction != Interaction::None && button.rect() != original;
         // any extra button with interaction will become the main button
         let rect_interacted = if interaction != Interaction::None {
             Some(button.rect())
@@ -158,83 +126,99 @@ pub async fn stage_torus() -> Button {
             None
         };
 
-        // render main and extra buttons
-        *button.rect_mut() = original;
-        render_button(&button);
-        for extra in &extra_buttons {
-            *button.rect_mut() = *extra;
-            render_button(&button);
-        }
-
-        // print dialogue if triggered
-        if let Some(start_dialogue_ts) = start_dialogue {
-            let current_ts = now();
-            if current_ts - start_dialogue_ts > DIALOG_DELAY_SECONDS {
-                start_dialogue = None;
-                if dialog_index == dialogs.len() - 1 {
-                    break;
-                }
-                dialog_index = (dialog_index + 1).min(dialogs.len() - 1);
-            } else {
-                let anchor = create_tooltip_anchor(sw, sh, button.rect());
-                render_tooltip(dialogs[dialog_index], anchor);
-                //         let anchor = center + match (horiz, vert) {
-                //     (Horizontal::Left, Vertical::Top) => Anchor::new(),
-                //     (Horizontal::Left, Vertical::Bottom) => 5.0,
-                //     (Horizontal::Right, Vertical::Top) => -5.0,
-                //     (Horizontal::Right, Vertical::Bottom) => -5.0,
-                //     _ => panic!(),
-                // };
-            }
+        let events = DialogEvents {
+            hovered: interaction != Interaction::None,
+            clicked: interaction.is_clicked(),
+        };
+        if self.dialog.step(events, dt) {
+            shared.audio.play(SoundEffect::Blip);
         }
-
-        // start dialogue with interacted
         *button.rect_mut() = original;
-        if let Some(interacted) = rect_interacted {
-            if start_dialogue.is_some() {
-                let prev_dialog_index = dialog_index;
-                dialog_index = (dialog_index + 1).min(dialogs.len() - 1);
-                if prev_dialog_index != dialog_index {
-                    start_dialogue = Some(now());
-                }
-            } else {
-                start_dialogue = Some(now());
-            }
-            *button.rect_mut() = interacted;
-        } else {
-            let screen = Rect::new(0.0, 0.0, sw, sh);
-            if screen.intersect(button.rect()).is_none() {
-                // no interaction and main button went out, so choose an extra as main
-                let mut max_area = 0.0;
-                for extra in extra_buttons {
-                    if let Some(intersection) = extra.intersect(screen) {
-                        let area = intersection.w * intersection.h;
-                        if area > max_area {
-                            max_area = area;
-                            *button.rect_mut() = extra;
-                        }
-                    }
-                }
+        if self.dialog.is_finished() {
+            if self.topology == Topology::Torus {
+                shared.achieve(Achievement::SmallTalk);
             }
+            return Some(StageOutcome::Next);
         }
 
+        // the interacted copy becomes the main button, or the one showing most if it went out
+        *button.rect_mut() = rect_interacted.unwrap_or_else(|| space.main_copy(button.rect()));
+        self.particles.step(dt);
+        let wrapped_to = button.rect().center();
+        if (wrapped_to - new_pos).length() > sw.min(sh) * 0.5 && !shared.settings.reduced_motion {
+            self.particles.emit(&SPARKS, new_pos, Vec2::X);
+            self.particles.emit(&SPARKS, wrapped_to, Vec2::X);
+        }
+        let text = shared.text(self.text_key);
+        self.ghosts = extra_buttons
+            .iter()
+            .map(|extra| shared.new_button(text, Anchor::center_v(extra.center()), input))
+            .collect();
+
         // should not happen
         if interaction.is_clicked() {
-            text = "Start again";
-            button = new_button(text, anchor);
+            if through_ghost {
+                shared.achieve(Achievement::ThroughTheWrap);
+            }
+            self.text_key = "start_again";
+            let anchor = Anchor::center_v(shared.screen_center());
+            shared.button = shared.new_button(shared.text(self.text_key), anchor, input);
+            shared.button_key = self.text_key;
         }
+        None
+    }
+
+    fn render(&self, shared: &Shared) {
+        let button = &shared.button;
+        clear_background(LIGHTGRAY);
+        if let Some(toggle_move) = &self.toggle_move {
+            render_button(toggle_move);
+        }
+        let diff = self.mouse_diff;
+        let alpha =
+            ((2.0 * FORCE_RANGE_PIXELS - diff) / (2.0 * FORCE_RANGE_PIXELS) / 3.0).clamp(0.0, 0.5);
+        // 2*RANGE => 0, 0.5*range => 1
+        let halo_color = with_alpha(GRAY, alpha);
+        draw_halo(self.new_pos.x, self.new_pos.y, diff, halo_color);
 
-        if debug {
-            draw_line(
-                button_center.x,
-                button_center.y,
-                new_pos.x,
-                new_pos.y,
-                2.0,
-                DARKGREEN,
-            );
+        // render main and extra buttons
+        render_button(button);
+        for ghost in &self.ghosts {
+            render_button(ghost);
         }
-        next_frame().await;
+        self.particles.render();
+
+        render_dialog(&self.dialog, &shared.strings, shared.screen, button.rect());
     }
-    button
+
+    fn inspect(&self, shared: &Shared, overlay: &mut DevOverlay) {
+        let space = TorusSpace::with_topology(shared.screen, self.topology);
+        overlay.field(FieldView {
+            stage: self.topology.name(),
+            mice: space.images(self.mouse_pos),
+            home: shared.screen_center(),
+            time: self.elapsed,
+        });
+        overlay.hitbox(Hitbox::Button, Shape::rect(shared.button.rect()));
+        for ghost in &self.ghosts {
+            overlay.hitbox(Hitbox::Ghost, Shape::rect(ghost.rect()));
+        }
+        overlay.hitbox(Hitbox::Mouse, cursor_shape(self.mouse_pos));
+        overlay.line(format!("button center: {}", self.button_center));
+        overlay.line(format!("pushed to: {}", self.new_pos));
+        overlay.line(format!("moving: {}", self.move_button));
+        overlay.line(format!("elapsed: {:.1}s", self.elapsed));
+        overlay.dialog(&self.dialog);
+    }
+}
+
+/// The mouse pushes the button from everywhere it is seen, through the edges too
+fn wrapped_force(shared: &Shared, space: &TorusSpace, mouse_pos: Vec2, time: f32, dt: f32) -> Vec2 {
+    let (stage, home) = (space.topology.name(), shared.screen_center());
+    space
+        .images(mouse_pos)
+        .into_iter()
+        .fold(Vec2::ZERO, |force, image| {
+            force + shared.push_button(stage, image, home, time, dt)
+        })
 }
@@ -0,0 +1,219 @@
+//! The kinds of rockets, how each of them steers, and how they look.
+
+use macroquad::color::{Color, DARKGRAY, MAROON, ORANGE, PURPLE, RED, WHITE};
+use macroquad::math::Vec2;
+use std::f32::consts::TAU;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RocketKind {
+    /// Chases the mouse and avoids the button
+    Homing,
+    /// Aims at the mouse when launched and never turns
+    Straight,
+    /// Homes in slowly while swinging from side to side
+    SineWave,
+    /// Homing, until it breaks into smaller homing rockets
+    Splitting,
+    /// Looks like a rocket, but can't hurt anything
+    Decoy,
+    /// Slow, but turns sharply and flies for a long time
+    Relentless,
+}
+
+pub const ALL_ROCKET_KINDS: [RocketKind; 6] = [
+    RocketKind::Homing,
+    RocketKind::Straight,
+    RocketKind::SineWave,
+    RocketKind::Splitting,
+    RocketKind::Decoy,
+    RocketKind::Relentless,
+];
+
+/// How strongly a rocket turns, relative to its speed
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Steering {
+    /// Turn rates per second. The homing ones were tuned as 0.3 and 0.23 per frame at 60 FPS.
+    pub to_mouse: f32,
+    pub from_button: f32,
+    /// Radians to each side of the heading
+    pub wobble: f32,
+    /// Swings per second
+    pub wobble_frequency: f32,
+}
+
+const HOMING: Steering = Steering {
+    to_mouse: 0.3 * 60.0,
+    from_button: 0.23 * 60.0,
+    wobble: 0.0,
+    wobble_frequency: 0.0,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Split {
+    /// Seconds after launch
+    pub after: f32,
+    pub into: usize,
+    pub child: RocketKind,
+    /// Radians between the headings of the children
+    pub spread: f32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Look {
+    /// Multiply the base length and width of a rocket
+    pub size: f32,
+    pub body: Color,
+    pub wings: Color,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct KindParams {
+    /// Multiplies the speed of the wave
+    pub speed: f32,
+    pub steering: Steering,
+    /// Multiplies the fuel of the wave
+    pub fuel: f32,
+    pub aims_at_launch: bool,
+    pub split: Option<Split>,
+    /// Whether it can hit the mouse or the button
+    pub harmful: bool,
+    pub look: Look,
+}
+
+const BASE: KindParams = KindParams {
+    speed: 1.0,
+    steering: HOMING,
+    fuel: 1.0,
+    aims_at_launch: false,
+    split: None,
+    harmful: true,
+    look: Look {
+        size: 1.0,
+        body: RED,
+        wings: WHITE,
+    },
+};
+
+impl RocketKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            RocketKind::Homing => "homing",
+            RocketKind::Straight => "straight",
+            RocketKind::SineWave => "sine_wave",
+            RocketKind::Splitting => "splitting",
+            RocketKind::Decoy => "decoy",
+            RocketKind::Relentless => "relentless",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        ALL_ROCKET_KINDS
+            .into_iter()
+            .find(|kind| kind.name() == name)
+    }
+
+    pub fn params(self) -> KindParams {
+        match self {
+            RocketKind::Homing => BASE,
+            RocketKind::Straight => KindParams {
+                speed: 1.2,
+                steering: Steering {
+                    to_mouse: 0.0,
+                    from_button: 0.0,
+                    ..HOMING
+                },
+                aims_at_launch: true,
+                look: Look {
+                    size: 0.8,
+                    body: ORANGE,
+                    ..BASE.look
+                },
+                ..BASE
+            },
+            RocketKind::SineWave => KindParams {
+                steering: Steering {
+                    to_mouse: HOMING.to_mouse * 0.5,
+                    wobble: 0.6,
+                    wobble_frequency: 1.5,
+                    ..HOMING
+                },
+                look: Look {
+                    body: PURPLE,
+                    ..BASE.look
+                },
+                ..BASE
+            },
+            RocketKind::Splitting => KindParams {
+                split: Some(Split {
+                    after: 1.5,
+                    into: 3,
+                    child: RocketKind::Homing,
+                    spread: 0.6,
+                }),
+                look: Look {
+                    size: 1.3,
+                    body: MAROON,
+                    ..BASE.look
+                },
+                ..BASE
+            },
+            RocketKind::Decoy => KindParams {
+                harmful: false,
+                look: Look {
+                    body: DARKGRAY,
+                    ..BASE.look
+                },
+                ..BASE
+            },
+            RocketKind::Relentless => KindParams {
+                speed: 0.45,
+                steering: Steering {
+                    to_mouse: HOMING.to_mouse * 2.0,
+                    from_button: HOMING.from_button * 0.5,
+                    ..HOMING
+                },
+                fuel: 3.0,
+                look: Look {
+                    size: 1.2,
+                    body: DARKGRAY,
+                    wings: RED,
+                },
+                ..BASE
+            },
+        }
+    }
+}
+
+impl Steering {
+    /// Turns the unit `heading` toward the mouse and away from the button. `turn` multiplies the
+    /// turn rates.
+    pub fn steer(
+        &self,
+        heading: Vec2,
+        pos: Vec2,
+        mouse: Vec2,
+        button: Vec2,
+        turn: f32,
+        dt: f32,
+    ) -> Vec2 {
+        let to_mouse = (mouse - pos).normalize_or_zero() * self.to_mouse;
+        let from_button = (pos - button).normalize_or_zero() * self.from_button;
+        let turned = heading + (to_mouse + from_button) * turn * dt;
+        turned.try_normalize().unwrap_or(heading)
+    }
+
+    /// Where the rocket actually flies, swinging around its heading
+    pub fn direction(&self, heading: Vec2, age: f32) -> Vec2 {
+        let swing = self.wobble * (TAU * self.wobble_frequency * age).sin();
+        heading.rotate(Vec2::from_angle(swing))
+    }
+}
+
+impl Split {
+    /// Fanned out around `heading`
+    pub fn headings(&self, heading: Vec2) -> Vec<Vec2> {
+        let middle = (self.into as f32 - 1.0) * 0.5;
+        (0..self.into)
+            .map(|i| heading.rotate(Vec2::from_angle((i as f32 - middle) * self.spread)))
+            .collect()
+    }
+}
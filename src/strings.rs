@@ -0,0 +1,125 @@
+//! Every text that the player reads, in every language. See `assets/lang/en.txt` for the format.
+
+use std::collections::HashMap;
+
+pub const FALLBACK_LANGUAGE: &str = "en";
+pub const LANGUAGES_DIR: &str = "assets/lang";
+const EMBEDDED_LANGUAGES: &[(&str, &str)] = &[
+    ("en", include_str!("../assets/lang/en.txt")),
+    ("es", include_str!("../assets/lang/es.txt")),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Strings {
+    language: String,
+    /// Language codes in a stable order, to cycle through them
+    codes: Vec<String>,
+    tables: HashMap<String, HashMap<String, String>>,
+}
+
+impl Strings {
+    pub fn embedded() -> Self {
+        let mut strings = Self {
+            language: FALLBACK_LANGUAGE.to_string(),
+            codes: Vec::new(),
+            tables: HashMap::new(),
+        };
+        for (code, text) in EMBEDDED_LANGUAGES {
+            let table = parse_table(text).expect("the embedded string tables should be valid");
+            strings.add_language(code, table);
+        }
+        strings
+    }
+
+    pub fn add_language(&mut self, code: &str, table: HashMap<String, String>) {
+        if !self.codes.iter().any(|c| c == code) {
+            self.codes.push(code.to_string());
+        }
+        self.tables.insert(code.to_string(), table);
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn languages(&self) -> &[String] {
+        &self.codes
+    }
+
+    /// Returns false and keeps the current language if there is no table for `code`
+    pub fn set_language(&mut self, code: &str) -> bool {
+        if self.tables.contains_key(code) {
+            self.language = code.to_string();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn next_language(&self) -> &str {
+        let current = self.codes.iter().position(|c| *c == self.language);
+        let next = current.map_or(0, |i| (i + 1) % self.codes.len());
+        &self.codes[next]
+    }
+
+    /// The text in the current language, or in the fallback language, or the key itself so that
+    /// missing translations are easy to spot
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.find(key).unwrap_or(key)
+    }
+
+    /// Like `get`, but with a default text instead of the key
+    pub fn get_or<'a>(&'a self, key: &str, default: &'a str) -> &'a str {
+        self.find(key).unwrap_or(default)
+    }
+
+    fn find(&self, key: &str) -> Option<&str> {
+        [self.language.as_str(), FALLBACK_LANGUAGE]
+            .iter()
+            .filter_map(|code| self.tables.get(*code))
+            .find_map(|table| table.get(key))
+            .map(|text| text.as_str())
+    }
+}
+
+/// `key = text` per line. Lines starting with `#` are comments.
+pub fn parse_table(text: &str) -> Result<HashMap<String, String>, String> {
+    let mut table = HashMap::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected `key = text`", i + 1))?;
+        table.insert(key.trim().to_string(), value.trim().to_string());
+    }
+    Ok(table)
+}
+
+/// The language of the system, if we have a table for it
+pub fn system_language(strings: &Strings) -> Option<String> {
+    let lang = std::env::var("LANG").ok()?;
+    let code = lang.get(..2)?.to_lowercase();
+    strings.languages().contains(&code).then_some(code)
+}
+
+/// Lets translators edit the files without recompiling
+pub async fn load_strings() -> Strings {
+    let mut strings = Strings::embedded();
+    for code in strings.languages().to_vec() {
+        let path = format!("{}/{}.txt", LANGUAGES_DIR, code);
+        match macroquad::file::load_string(&path).await {
+            Ok(text) => match parse_table(&text) {
+                Ok(table) => strings.add_language(&code, table),
+                Err(e) => println!("could not parse {}: {}", path, e),
+            },
+            Err(e) => println!("could not load {}, using the embedded one: {}", path, e),
+        }
+    }
+    if let Some(code) = system_language(&strings) {
+        strings.set_language(&code);
+    }
+    strings
+}
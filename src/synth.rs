@@ -0,0 +1,143 @@
+//! A tiny synthesizer, so that the sound effects are generated in code instead of shipped as files.
+
+use std::f32::consts::TAU;
+
+pub const SAMPLE_RATE: u32 = 22050;
+
+/// Deterministic white noise, so that every run sounds the same
+pub struct Noise {
+    state: u32,
+}
+
+impl Noise {
+    pub fn new(seed: u32) -> Self {
+        Self { state: seed.max(1) }
+    }
+    /// In [-1, 1]
+    pub fn sample(&mut self) -> f32 {
+        // xorshift32
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+/// Keeps the phase continuous when the frequency changes from sample to sample
+#[derive(Default)]
+pub struct Oscillator {
+    phase: f32,
+}
+
+impl Oscillator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Returns the phase in [0, 1) for this sample and advances it
+    pub fn advance(&mut self, frequency: f32, sample_rate: u32) -> f32 {
+        let phase = self.phase;
+        self.phase = (self.phase + frequency / sample_rate as f32).fract();
+        phase
+    }
+}
+
+pub fn sine(phase: f32) -> f32 {
+    (phase * TAU).sin()
+}
+pub fn square(phase: f32) -> f32 {
+    if phase.fract() < 0.5 {
+        1.0
+    } else {
+        -1.0
+    }
+}
+pub fn saw(phase: f32) -> f32 {
+    phase.fract() * 2.0 - 1.0
+}
+
+/// One-pole low-pass filter, to turn noise into rumbles and whooshes
+#[derive(Default)]
+pub struct LowPass {
+    last: f32,
+}
+
+impl LowPass {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn filter(&mut self, input: f32, cutoff: f32, sample_rate: u32) -> f32 {
+        let rc = 1.0 / (TAU * cutoff);
+        let dt = 1.0 / sample_rate as f32;
+        self.last += dt / (rc + dt) * (input - self.last);
+        self.last
+    }
+}
+
+/// Linear attack, then an exponential decay that falls to about a third every `decay` seconds
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Envelope {
+    pub attack: f32,
+    pub decay: f32,
+}
+
+impl Envelope {
+    pub fn gain(&self, time: f32) -> f32 {
+        if time < self.attack {
+            time / self.attack
+        } else {
+            (-(time - self.attack) / self.decay).exp()
+        }
+    }
+}
+
+/// Calls `sample` with the time in seconds of every sample
+pub fn render(seconds: f32, sample_rate: u32, mut sample: impl FnMut(f32) -> f32) -> Vec<f32> {
+    let count = (seconds * sample_rate as f32) as usize;
+    (0..count)
+        .map(|i| sample(i as f32 / sample_rate as f32))
+        .collect()
+}
+
+/// Scales the clip so that its loudest sample is `peak`
+pub fn normalize(samples: &mut [f32], peak: f32) {
+    let loudest = samples.iter().fold(0.0_f32, |max, s| max.max(s.abs()));
+    if loudest > 0.0 {
+        for sample in samples {
+            *sample *= peak / loudest;
+        }
+    }
+}
+
+/// Short fades at both ends, so that the clip starts and stops without clicking
+pub fn fade_edges(samples: &mut [f32], fade_samples: usize) {
+    let fade = fade_samples.min(samples.len() / 2);
+    let len = samples.len();
+    for i in 0..fade {
+        let gain = i as f32 / fade as f32;
+        samples[i] *= gain;
+        samples[len - 1 - i] *= gain;
+    }
+}
+
+/// 16 bit mono PCM, which is what the audio backends can load
+pub fn encode_wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let data_len = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16_u32.to_le_bytes());
+    wav.extend_from_slice(&1_u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1_u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2_u16.to_le_bytes());
+    wav.extend_from_slice(&16_u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        wav.extend_from_slice(&value.to_le_bytes());
+    }
+    wav
+}
@@ -0,0 +1,91 @@
+use crate::PixelPosition;
+use juquad::input::input_trait::InputTrait;
+use macroquad::input::{KeyCode, MouseButton};
+
+/// All the simulation advances in steps of this size, regardless of the refresh rate
+pub const FIXED_DT: f32 = 1.0 / 60.0;
+/// After a long hitch (like dragging the window) we drop time instead of trying to catch up
+const MAX_STEPS_PER_FRAME: u32 = 8;
+
+#[derive(Default)]
+pub struct FixedTimestep {
+    accumulated: f32,
+}
+
+impl FixedTimestep {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns how many steps of FIXED_DT should run for a frame that took `frame_time` seconds.
+    ///
+    /// With `has_events`, a frame that would run no steps runs one anyway, borrowing time from
+    /// the next frame, so that clicks and key presses are never lost on fast monitors.
+    pub fn advance(&mut self, frame_time: f32, has_events: bool) -> u32 {
+        self.accumulated += frame_time;
+        let mut steps = (self.accumulated / FIXED_DT) as u32;
+        if steps == 0 && has_events {
+            steps = 1;
+        }
+        let steps = steps.min(MAX_STEPS_PER_FRAME);
+        self.accumulated -= steps as f32 * FIXED_DT;
+        self.accumulated = self.accumulated.clamp(-FIXED_DT, FIXED_DT);
+        steps
+    }
+}
+
+/// Input for the extra steps of a frame: same mouse and held keys, but the press and release
+/// events were already seen by the first step.
+pub struct HeldInput {
+    inner: Box<dyn InputTrait>,
+}
+impl HeldInput {
+    pub fn new(inner: &Box<dyn InputTrait>) -> Self {
+        Self {
+            inner: (*inner).clone(),
+        }
+    }
+}
+impl InputTrait for HeldInput {
+    fn is_key_down(&self, key: KeyCode) -> bool {
+        self.inner.is_key_down(key)
+    }
+
+    fn is_key_pressed(&self, _key: KeyCode) -> bool {
+        false
+    }
+
+    fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.inner.is_mouse_button_down(button)
+    }
+
+    fn is_mouse_button_pressed(&self, _button: MouseButton) -> bool {
+        false
+    }
+
+    fn is_mouse_button_released(&self, _button: MouseButton) -> bool {
+        false
+    }
+
+    fn mouse_position(&self) -> PixelPosition {
+        self.inner.mouse_position()
+    }
+
+    fn mouse_wheel(&self) -> PixelPosition {
+        self.inner.mouse_wheel()
+    }
+
+    fn clone(&self) -> Box<dyn InputTrait> {
+        Box::new(HeldInput {
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub fn has_events(input: &Box<dyn InputTrait>, key_pressed: bool) -> bool {
+    let buttons = [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+    key_pressed
+        || buttons.iter().any(|button| {
+            input.is_mouse_button_pressed(*button) || input.is_mouse_button_released(*button)
+        })
+}
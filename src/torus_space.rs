@@ -0,0 +1,226 @@
+//! A rectangle whose opposite edges are glued together, so whatever leaves through one edge comes
+//! back through the other. Other topologies glue them flipped, or not at all.
+
+use macroquad::math::{Rect, Vec2};
+
+/// In units of the space size, in the order that the copies are returned
+const COPY_OFFSETS: [(f32, f32); 8] = [
+    (1.0, 0.0),
+    (0.0, 1.0),
+    (-1.0, 0.0),
+    (0.0, -1.0),
+    (1.0, 1.0),
+    (-1.0, 1.0),
+    (-1.0, -1.0),
+    (1.0, -1.0),
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Topology {
+    Torus,
+    /// Crossing the left or right edge mirrors the vertical position
+    KleinBottle,
+    /// Crossing any edge mirrors the position along the other axis
+    ProjectivePlane,
+    /// Wraps horizontally, with hard walls at the top and bottom
+    Cylinder,
+    /// Nothing wraps, and the edges reflect what is inside
+    MirrorWalls,
+}
+
+/// What the two edges across one axis do
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Edges {
+    Wrap,
+    /// Wrap, mirroring the other axis
+    FlipWrap,
+    Wall,
+    Mirror,
+}
+
+impl Topology {
+    /// Stable names, also used for the dialog of each variant of the wrap stage
+    pub fn name(self) -> &'static str {
+        match self {
+            Topology::Torus => "torus",
+            Topology::KleinBottle => "klein_bottle",
+            Topology::ProjectivePlane => "projective_plane",
+            Topology::Cylinder => "cylinder",
+            Topology::MirrorWalls => "mirror_walls",
+        }
+    }
+
+    /// The left and right edges, then the top and bottom ones
+    pub fn edges(self) -> (Edges, Edges) {
+        match self {
+            Topology::Torus => (Edges::Wrap, Edges::Wrap),
+            Topology::KleinBottle => (Edges::FlipWrap, Edges::Wrap),
+            Topology::ProjectivePlane => (Edges::FlipWrap, Edges::FlipWrap),
+            Topology::Cylinder => (Edges::Wrap, Edges::Wall),
+            Topology::MirrorWalls => (Edges::Mirror, Edges::Mirror),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TorusSpace {
+    pub size: Vec2,
+    pub topology: Topology,
+}
+
+impl TorusSpace {
+    pub fn new(size: Vec2) -> Self {
+        Self::with_topology(size, Topology::Torus)
+    }
+
+    pub fn with_topology(size: Vec2, topology: Topology) -> Self {
+        Self { size, topology }
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0.0, 0.0, self.size.x, self.size.y)
+    }
+
+    /// Whether some area of `rect` is inside the space
+    pub fn shows(&self, rect: Rect) -> bool {
+        rect.left() < self.size.x
+            && rect.right() > 0.0
+            && rect.top() < self.size.y
+            && rect.bottom() > 0.0
+    }
+
+    fn edges(&self, axis: usize) -> Edges {
+        let (x, y) = self.topology.edges();
+        if axis == 0 {
+            x
+        } else {
+            y
+        }
+    }
+
+    /// Where `pos` is seen from the other side of an edge. `side` is -1 or 1 along `axis`, where
+    /// 0 is x. Walls can't be seen through.
+    fn cross(&self, mut pos: Vec2, axis: usize, side: f32) -> Option<Vec2> {
+        let size = self.size[axis];
+        let other = 1 - axis;
+        match self.edges(axis) {
+            Edges::Wrap => pos[axis] += side * size,
+            Edges::FlipWrap => {
+                pos[axis] += side * size;
+                pos[other] = self.size[other] - pos[other];
+            }
+            Edges::Mirror => pos[axis] = size * (side + 1.0) - pos[axis],
+            Edges::Wall => return None,
+        }
+        Some(pos)
+    }
+
+    /// Seen through the horizontal edges first, then the vertical ones
+    fn cross_both(&self, pos: Vec2, (x, y): (f32, f32)) -> Option<Vec2> {
+        let pos = if x != 0.0 {
+            self.cross(pos, 0, x)?
+        } else {
+            pos
+        };
+        if y != 0.0 {
+            self.cross(pos, 1, y)
+        } else {
+            Some(pos)
+        }
+    }
+
+    /// `pos` and where it is seen through each edge and corner
+    pub fn images(&self, pos: Vec2) -> Vec<Vec2> {
+        let crossed = COPY_OFFSETS
+            .iter()
+            .filter_map(|offset| self.cross_both(pos, *offset));
+        std::iter::once(pos).chain(crossed).collect()
+    }
+
+    /// The copies of `rect` that show through the opposite edges and corners, for the parts of it
+    /// that stick out. A rect fully inside has none.
+    pub fn wrapped_copies(&self, rect: Rect) -> Vec<Rect> {
+        let half = rect.size() * 0.5;
+        COPY_OFFSETS
+            .iter()
+            .filter_map(|offset| self.cross_both(rect.center(), *offset))
+            .map(|center| Rect::new(center.x - half.x, center.y - half.y, rect.w, rect.h))
+            .filter(|copy| self.shows(*copy))
+            .collect()
+    }
+
+    /// Pushed back inside across the edges that don't wrap
+    pub fn confine(&self, mut rect: Rect) -> Rect {
+        for axis in 0..2 {
+            if let Edges::Wall | Edges::Mirror = self.edges(axis) {
+                let (start, len) = if axis == 0 {
+                    (&mut rect.x, rect.w)
+                } else {
+                    (&mut rect.y, rect.h)
+                };
+                *start = start.clamp(0.0, (self.size[axis] - len).max(0.0));
+            }
+        }
+        rect
+    }
+
+    /// Confined, and `rect` itself while some of it is inside, otherwise the copy that shows the
+    /// most
+    pub fn main_copy(&self, rect: Rect) -> Rect {
+        let rect = self.confine(rect);
+        if self.shows(rect) {
+            return rect;
+        }
+        let bounds = self.bounds();
+        let area = |copy: &Rect| {
+            copy.intersect(bounds)
+                .map_or(0.0, |inside| inside.w * inside.h)
+        };
+        self.wrapped_copies(rect)
+            .into_iter()
+            .max_by(|a, b| area(a).total_cmp(&area(b)))
+            .unwrap_or(rect)
+    }
+
+    /// The same point, inside the space
+    pub fn normalize(&self, mut pos: Vec2) -> Vec2 {
+        for axis in 0..2 {
+            let size = self.size[axis];
+            let other = 1 - axis;
+            match self.edges(axis) {
+                Edges::Wrap => pos[axis] = pos[axis].rem_euclid(size),
+                Edges::FlipWrap => {
+                    let crossings = (pos[axis] / size).floor();
+                    pos[axis] -= crossings * size;
+                    if crossings.rem_euclid(2.0) == 1.0 {
+                        pos[other] = self.size[other] - pos[other];
+                    }
+                }
+                Edges::Mirror => {
+                    let folded = pos[axis].rem_euclid(2.0 * size);
+                    pos[axis] = size - (folded - size).abs();
+                }
+                Edges::Wall => pos[axis] = pos[axis].clamp(0.0, size),
+            }
+        }
+        pos
+    }
+
+    /// The shortest way from `from` to `to`, maybe through the edges
+    pub fn wrapped_delta(&self, from: Vec2, to: Vec2) -> Vec2 {
+        self.images(to)
+            .into_iter()
+            .map(|image| image - from)
+            .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+            .unwrap_or(to - from)
+    }
+
+    pub fn wrapped_distance(&self, from: Vec2, to: Vec2) -> f32 {
+        self.wrapped_delta(from, to).length()
+    }
+
+    /// Normalized, or zero if both points are the same
+    pub fn wrapped_direction(&self, from: Vec2, to: Vec2) -> Vec2 {
+        self.wrapped_delta(from, to).normalize_or_zero()
+    }
+}
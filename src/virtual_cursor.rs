@@ -0,0 +1,79 @@
+use crate::geometry::Shape;
+use macroquad::color::{BLACK, WHITE};
+use macroquad::math::{vec2, Rect, Vec2};
+use macroquad::prelude::{draw_triangle, draw_triangle_lines};
+
+const CURSOR_HEIGHT: f32 = 18.0;
+const CURSOR_WIDTH: f32 = 12.0;
+
+/// A pointer that only moves by the deltas of the real mouse and can't leave its bounds.
+///
+/// While the cursor is grabbed, macroquad keeps accumulating raw mouse motion into an unbounded
+/// `mouse_position()`, so we only look at how much it changed since the previous frame.
+pub struct VirtualCursor {
+    pos: Vec2,
+    last_raw: Option<Vec2>,
+    bounds: Rect,
+    pub sensitivity: f32,
+}
+
+impl VirtualCursor {
+    pub fn new(pos: Vec2, bounds: Rect) -> Self {
+        Self {
+            pos: clamp_to_rect(pos, bounds),
+            last_raw: None,
+            bounds,
+            sensitivity: 1.0,
+        }
+    }
+    pub fn pos(&self) -> Vec2 {
+        self.pos
+    }
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+    pub fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+        self.pos = clamp_to_rect(self.pos, bounds);
+    }
+    /// Call once per frame with the raw mouse position. The first call only sets the reference.
+    pub fn accumulate(&mut self, raw: Vec2) {
+        if let Some(last_raw) = self.last_raw {
+            self.pos = clamp_to_rect(self.pos + (raw - last_raw) * self.sensitivity, self.bounds);
+        }
+        self.last_raw = Some(raw);
+    }
+    /// Ignores how much the mouse moved since the last `accumulate`, like while the game was
+    /// paused and the mouse was free
+    pub fn reset_reference(&mut self) {
+        self.last_raw = None;
+    }
+}
+
+/// A rect with a negative size, like the bounds of a minimized window, clamps to its corner
+pub fn clamp_to_rect(pos: Vec2, rect: Rect) -> Vec2 {
+    vec2(
+        pos.x.min(rect.right()).max(rect.x),
+        pos.y.min(rect.bottom()).max(rect.y),
+    )
+}
+
+/// The tip, bottom and side of an arrow pointer like the one of the OS
+fn cursor_points(pos: Vec2) -> (Vec2, Vec2, Vec2) {
+    let tip = pos;
+    let bottom = pos + vec2(0.0, CURSOR_HEIGHT);
+    let side = pos + vec2(CURSOR_WIDTH, CURSOR_HEIGHT * 0.7);
+    (tip, bottom, side)
+}
+
+/// For hit tests against the pointer at `pos`
+pub fn cursor_shape(pos: Vec2) -> Shape {
+    let (tip, bottom, side) = cursor_points(pos);
+    Shape::triangle(tip, bottom, side)
+}
+
+pub fn render_cursor(pos: Vec2) {
+    let (tip, bottom, side) = cursor_points(pos);
+    draw_triangle(tip, bottom, side, WHITE);
+    draw_triangle_lines(tip, bottom, side, 1.5, BLACK);
+}
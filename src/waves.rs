@@ -0,0 +1,169 @@
+//! When the rockets stage launches rockets, and how dangerous they are, wave after wave. The kinds
+//! of rockets of each wave are loaded from a text file, see `assets/waves.txt` for the format.
+
+use crate::steering::RocketKind;
+
+pub const WAVES_PATH: &str = "assets/waves.txt";
+pub const DEFAULT_WAVES: &str = include_str!("../assets/waves.txt");
+/// Seconds between the last rocket of a wave burning out and the first one of the next
+pub const WAVE_BREAK_SECONDS: f32 = 2.0;
+const MAX_ROCKETS: usize = 8;
+
+/// The rockets of each wave take these kinds in turns. Later waves repeat the last mix.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WaveMixes(Vec<Vec<RocketKind>>);
+
+impl WaveMixes {
+    pub fn embedded() -> Self {
+        Self::parse(DEFAULT_WAVES).expect("the embedded waves should be valid")
+    }
+
+    /// The mix of the wave `number`, starting at 1
+    pub fn kinds(&self, number: usize) -> &[RocketKind] {
+        &self.0[number.clamp(1, self.0.len()) - 1]
+    }
+
+    /// One wave per line, as the names of its rocket kinds
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut mixes = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mix = line
+                .split_whitespace()
+                .map(|word| {
+                    RocketKind::from_name(word)
+                        .ok_or_else(|| format!("line {}: unknown rocket kind {}", i + 1, word))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            mixes.push(mix);
+        }
+        if mixes.is_empty() {
+            return Err("expected at least one wave".to_string());
+        }
+        Ok(Self(mixes))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wave {
+    /// Starting at 1
+    pub number: usize,
+    pub rockets: usize,
+    /// Seconds between launches
+    pub interval: f32,
+    /// Multiplies the speed of the rockets
+    pub speed: f32,
+    /// Multiplies how fast the rockets steer
+    pub turn: f32,
+    /// Seconds that every rocket flies before burning out
+    pub fuel: f32,
+    pub kinds: Vec<RocketKind>,
+}
+
+impl Wave {
+    /// The difficulty curve. The first wave is a single rocket at the base speed.
+    pub fn number(number: usize, mixes: &WaveMixes) -> Self {
+        let level = number.saturating_sub(1) as f32;
+        Self {
+            number,
+            rockets: number.clamp(1, MAX_ROCKETS),
+            interval: (1.5 - 0.15 * level).max(0.5),
+            speed: (1.0 + 0.08 * level).min(1.6),
+            turn: (1.0 + 0.1 * level).min(1.8),
+            fuel: 8.0,
+            kinds: mixes.kinds(number).to_vec(),
+        }
+    }
+
+    /// The kind of the `index`th rocket launched in this wave
+    pub fn kind(&self, index: usize) -> RocketKind {
+        self.kinds[index % self.kinds.len()]
+    }
+}
+
+/// Launches the rockets of each wave one by one, and starts the next wave after a break once
+/// they are all gone
+pub struct Waves {
+    mixes: WaveMixes,
+    wave: Wave,
+    launched: usize,
+    until_launch: f32,
+    /// Seconds left of the break before the next wave
+    break_left: Option<f32>,
+}
+
+impl Default for Waves {
+    fn default() -> Self {
+        Self::new(WaveMixes::embedded())
+    }
+}
+
+impl Waves {
+    pub fn new(mixes: WaveMixes) -> Self {
+        Self {
+            wave: Wave::number(1, &mixes),
+            mixes,
+            launched: 0,
+            until_launch: 0.0,
+            break_left: None,
+        }
+    }
+
+    pub fn current(&self) -> &Wave {
+        &self.wave
+    }
+
+    /// Rockets launched so far in the current wave
+    pub fn launched(&self) -> usize {
+        self.launched
+    }
+
+    pub fn in_break(&self) -> bool {
+        self.break_left.is_some()
+    }
+
+    /// `flying` is how many rockets are still alive. Returns true when a rocket should launch.
+    pub fn step(&mut self, dt: f32, flying: usize) -> bool {
+        if let Some(break_left) = self.break_left.as_mut() {
+            *break_left -= dt;
+            if *break_left <= 0.0 {
+                self.wave = Wave::number(self.wave.number + 1, &self.mixes);
+                self.launched = 0;
+                self.until_launch = 0.0;
+                self.break_left = None;
+            }
+            false
+        } else if self.launched < self.wave.rockets {
+            self.until_launch -= dt;
+            let launch = self.until_launch <= 0.0;
+            if launch {
+                self.launched += 1;
+                self.until_launch = self.wave.interval;
+            }
+            launch
+        } else {
+            if flying == 0 {
+                self.break_left = Some(WAVE_BREAK_SECONDS);
+            }
+            false
+        }
+    }
+}
+
+/// Lets designers edit the file next to the executable without recompiling
+pub async fn load_waves() -> WaveMixes {
+    match macroquad::file::load_string(WAVES_PATH).await {
+        Ok(text) => match WaveMixes::parse(&text) {
+            Ok(waves) => return waves,
+            Err(e) => println!("could not parse {}: {}", WAVES_PATH, e),
+        },
+        Err(e) => println!(
+            "could not load {}, using the embedded waves: {}",
+            WAVES_PATH, e
+        ),
+    }
+    WaveMixes::embedded()
+}
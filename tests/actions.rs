@@ -0,0 +1,36 @@
+use macroquad::input::{KeyCode, MouseButton};
+use press_to_start::actions::{Action, Binding, Bindings};
+
+#[test]
+fn test_bindings_round_trip() {
+    let mut bindings = Bindings::default();
+    bindings
+        .rebind(Action::Pause, Binding::Mouse(MouseButton::Right))
+        .unwrap();
+    bindings
+        .rebind(Action::Restart, Binding::Key(KeyCode::F5))
+        .unwrap();
+    assert_eq!(Bindings::parse(&bindings.serialize()), Ok(bindings));
+}
+
+#[test]
+fn test_missing_actions_keep_the_defaults() {
+    let bindings = Bindings::parse("# only quit\nquit = Q\n").unwrap();
+    assert_eq!(bindings.get(Action::Quit), &[Binding::Key(KeyCode::Q)]);
+    assert_eq!(bindings.get(Action::Restart), &[Binding::Key(KeyCode::R)]);
+}
+
+#[test]
+fn test_rebinding_to_a_used_key_is_rejected() {
+    let mut bindings = Bindings::default();
+    let result = bindings.rebind(Action::Quit, Binding::Key(KeyCode::R));
+    assert_eq!(result, Err(Action::Restart));
+    assert_eq!(bindings.get(Action::Quit), &[Binding::Key(KeyCode::Escape)]);
+}
+
+#[test]
+fn test_conflicts_in_the_file_are_rejected() {
+    assert!(Bindings::parse("quit = R\n").is_err());
+    assert!(Bindings::parse("quit = Escape, MouseLeft\n").is_err());
+    assert!(Bindings::parse("jump = Space\n").is_err());
+}
@@ -0,0 +1,93 @@
+use macroquad::input::KeyCode;
+use macroquad::math::vec2;
+use press_to_start::audio::{
+    Mixer, NullBackend, SoundBank, SoundEffect, Volumes, ALL_SOUND_EFFECTS, MAX_QUEUED,
+};
+use press_to_start::bot::BotAction::*;
+use press_to_start::bot::{run_bot, Bot};
+use press_to_start::measure_text_headless;
+use press_to_start::stages::driver::{Shared, Simulation, StageGraph, StageId};
+use press_to_start::synth::{encode_wav, SAMPLE_RATE};
+use press_to_start::timestep::FIXED_DT;
+
+fn seconds(seconds: f64) -> usize {
+    (seconds / FIXED_DT as f64).ceil() as usize
+}
+
+#[test]
+fn test_synthesized_effects_are_audible_and_in_range() {
+    for effect in ALL_SOUND_EFFECTS {
+        let samples = effect.synthesize(SAMPLE_RATE);
+        assert!(!samples.is_empty(), "{:?}", effect);
+        let peak = samples.iter().fold(0.0_f32, |max, s| max.max(s.abs()));
+        assert!(peak > 0.5 && peak <= 1.0, "{:?} peaks at {}", effect, peak);
+        assert!(samples[0].abs() < 0.01, "{:?} starts with a click", effect);
+        assert_eq!(samples, effect.synthesize(SAMPLE_RATE));
+    }
+}
+
+#[test]
+fn test_wav_header() {
+    let wav = encode_wav(&[0.0, 1.0, -1.0], 8000);
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(wav.len(), 44 + 3 * 2);
+    assert_eq!(i16::from_le_bytes([wav[46], wav[47]]), i16::MAX);
+}
+
+#[test]
+fn test_mixer_applies_category_volumes() {
+    let mut mixer = Mixer::new();
+    let volumes = Volumes {
+        master: 0.5,
+        effects: 0.5,
+        dialog: 0.0,
+    };
+    mixer.play(SoundEffect::Explosion);
+    mixer.play(SoundEffect::Blip);
+    let mut backend = NullBackend::default();
+    mixer.flush(&volumes, &mut backend);
+    assert_eq!(backend.played, vec![(SoundEffect::Explosion, 0.25)]);
+    assert!(mixer.queued().is_empty());
+}
+
+#[test]
+fn test_mixer_drops_repeated_requests() {
+    let mut mixer = Mixer::new();
+    mixer.play(SoundEffect::Whoosh);
+    mixer.step(0.1);
+    mixer.play(SoundEffect::Whoosh);
+    assert_eq!(mixer.queued(), &[SoundEffect::Whoosh]);
+    mixer.step(SoundEffect::Whoosh.min_interval());
+    mixer.play(SoundEffect::Whoosh);
+    assert_eq!(mixer.queued(), &[SoundEffect::Whoosh, SoundEffect::Whoosh]);
+}
+
+#[test]
+fn test_unflushed_queue_stays_bounded() {
+    // dying over and over, and nothing flushes the mixer
+    let lives = (0..10).flat_map(|_| [Wait(seconds(10.0)), Press(KeyCode::R)]);
+    let mut bot = Bot::new(vec2(100.0, 100.0), lives.collect());
+    let mut graph = StageGraph::campaign();
+    graph.start = StageId::Rockets;
+    let shared = Shared::new(vec2(800.0, 600.0), measure_text_headless, &bot.input());
+    let mut simulation = Simulation::new(graph, shared);
+    run_bot(&mut simulation, &mut bot, 100_000);
+    assert_eq!(simulation.history.len(), 21);
+    assert_eq!(simulation.shared.audio.queued().len(), MAX_QUEUED);
+}
+
+#[test]
+fn test_mixing_overlapping_clips() {
+    let bank = SoundBank::synthesize(SAMPLE_RATE);
+    let blip = bank.clip(SoundEffect::Blip);
+    let mixed = bank.mix(&[(0, SoundEffect::Blip, 0.5), (10, SoundEffect::Blip, 0.5)]);
+    assert_eq!(mixed.len(), blip.len() + 10);
+    assert_eq!(mixed[5], blip[5] * 0.5);
+    assert_eq!(mixed[20], (blip[20] + blip[10]) * 0.5);
+    let loud = bank.mix(&[
+        (0, SoundEffect::Explosion, 1.0),
+        (0, SoundEffect::Explosion, 1.0),
+    ]);
+    assert!(loud.iter().all(|s| s.abs() <= 1.0));
+}
@@ -0,0 +1,75 @@
+use macroquad::input::KeyCode;
+use macroquad::math::{vec2, Vec2};
+use press_to_start::bot::BotAction::*;
+use press_to_start::bot::{run_bot, Bot, BotAction};
+use press_to_start::dev_overlay::Hitbox;
+use press_to_start::measure_text_headless;
+use press_to_start::stages::driver::{Shared, Simulation, StageGraph, StageId};
+use press_to_start::timestep::FIXED_DT;
+
+const SCREEN: Vec2 = vec2(800.0, 600.0);
+
+fn seconds(seconds: f64) -> usize {
+    (seconds / FIXED_DT as f64).ceil() as usize
+}
+
+fn run(start: StageId, mouse: Vec2, script: Vec<BotAction>) -> Simulation {
+    let mut bot = Bot::new(mouse, script);
+    let mut graph = StageGraph::campaign();
+    graph.start = start;
+    let shared = Shared::new(SCREEN, measure_text_headless, &bot.input());
+    let mut simulation = Simulation::new(graph, shared);
+    run_bot(&mut simulation, &mut bot, 10_000);
+    simulation
+}
+
+fn has_hitbox(simulation: &Simulation, hitbox: Hitbox) -> bool {
+    let overlay = simulation.dev_overlay().unwrap();
+    overlay.hitboxes.iter().any(|(h, _)| *h == hitbox)
+}
+
+#[test]
+fn test_overlay_key_toggles_it() {
+    let simulation = run(StageId::Torus, vec2(100.0, 100.0), vec![Wait(1)]);
+    assert!(simulation.dev_overlay().is_none());
+
+    let simulation = run(StageId::Torus, vec2(100.0, 100.0), vec![Press(KeyCode::F3)]);
+    assert!(simulation.shared.dev_overlay);
+    let overlay = simulation.dev_overlay().unwrap();
+    assert_eq!(overlay.lines[0], "stage: torus");
+
+    let script = vec![Press(KeyCode::F3), Press(KeyCode::F3)];
+    let simulation = run(StageId::Torus, vec2(100.0, 100.0), script);
+    assert!(simulation.dev_overlay().is_none());
+}
+
+#[test]
+fn test_torus_overlay_shows_the_field_around_the_mouse() {
+    let mouse = vec2(100.0, 100.0);
+    let simulation = run(StageId::Torus, mouse, vec![Press(KeyCode::F3), Wait(1)]);
+    assert!(has_hitbox(&simulation, Hitbox::Button));
+    assert!(has_hitbox(&simulation, Hitbox::Mouse));
+    let overlay = simulation.dev_overlay().unwrap();
+    assert!(overlay
+        .lines
+        .iter()
+        .any(|line| line.starts_with("dialog: ")));
+
+    let shared = &simulation.shared;
+    let pushed = overlay
+        .field_velocity(shared, mouse + vec2(20.0, 0.0))
+        .unwrap();
+    assert!(pushed.x > 0.0);
+    let far = overlay.field_velocity(shared, vec2(400.0, 300.0)).unwrap();
+    assert_eq!(far, Vec2::ZERO);
+}
+
+#[test]
+fn test_rockets_overlay_shows_the_rockets() {
+    let script = vec![Press(KeyCode::F3), Wait(seconds(0.8))];
+    let simulation = run(StageId::Rockets, vec2(10.0, 10.0), script);
+    assert_eq!(simulation.current(), Some(StageId::Rockets));
+    assert!(has_hitbox(&simulation, Hitbox::Rocket));
+    let overlay = simulation.dev_overlay().unwrap();
+    assert!(overlay.lines.iter().any(|line| line.starts_with("wave 1")));
+}
@@ -0,0 +1,177 @@
+use press_to_start::dialog::{DialogEvents, DialogRunner, Dialogs, Speaker};
+
+const SCRIPT: &str = r#"
+# a comment
+[greeting]
+line start 0 ""
+    on hover goto hello
+line hello 2 @center "Hello!"
+    on click goto bye
+    on done hover goto bye
+line bye forever "Bye."
+    on idle 3 goto end
+    on after 10 goto hello
+
+[other]
+line only 1 "Only line."
+"#;
+
+const IDLE: DialogEvents = DialogEvents {
+    hovered: false,
+    clicked: false,
+};
+const HOVER: DialogEvents = DialogEvents {
+    hovered: true,
+    clicked: false,
+};
+const CLICK: DialogEvents = DialogEvents {
+    hovered: true,
+    clicked: true,
+};
+
+fn greeting() -> DialogRunner {
+    Dialogs::parse(SCRIPT).unwrap().runner("greeting")
+}
+
+#[test]
+fn test_parses_sections_lines_and_branches() {
+    let dialogs = Dialogs::parse(SCRIPT).unwrap();
+    let mut runner = dialogs.runner("greeting");
+    assert_eq!(runner.current_id(), Some("start"));
+    assert!(runner.visible_line().is_none());
+    runner.step(HOVER, 0.1);
+    let line = runner.visible_line().unwrap();
+    assert_eq!(line.id, "hello");
+    assert_eq!(line.text, "Hello!");
+    assert_eq!(line.duration, Some(2.0));
+    assert_eq!(line.speaker, Speaker::Center);
+    assert_eq!(line.branches.len(), 2);
+
+    let other = dialogs.runner("other");
+    assert_eq!(other.visible_line().unwrap().speaker, Speaker::Button);
+    assert!(dialogs.runner("missing").is_finished());
+}
+
+#[test]
+fn test_malformed_scripts_are_errors_with_the_line() {
+    for (script, error) in [
+        ("line a 1 \"x\"\n", "line 1: expected a [section] first"),
+        ("[s]\non hover goto end\n", "line 2: 'on' before any 'line'"),
+        ("[s]\nline a 1 x\n", "line 2: expected the text in quotes"),
+        (
+            "[s]\nline a 1 \"x\n",
+            "line 2: the text should end with a quote",
+        ),
+        (
+            "[s]\nline a 1 @left \"x\"\n",
+            "line 2: unknown speaker @left",
+        ),
+        ("[s]\nline a \"x\"\n", "line 2: expected: line"),
+        ("[s]\nline a soon \"x\"\n", "line 2: soon: "),
+        (
+            "[s]\nline a 1 \"x\"\n  on wiggle goto end\n",
+            "line 3: unknown condition wiggle",
+        ),
+        (
+            "[s]\nline a 1 \"x\"\n  on idle goto end\n",
+            "line 3: expected the seconds",
+        ),
+        ("[s]\nline a 1 \"x\"\n  on hover\n", "line 3: expected: on"),
+        ("[s]\nsay \"x\"\n", "line 2: unknown statement"),
+        (
+            "[s]\nline a 1 \"x\"\n  on hover goto b\n",
+            "line a goes to unknown line b",
+        ),
+    ] {
+        let result = Dialogs::parse(script);
+        let message = result.err().unwrap_or_default();
+        assert!(message.starts_with(error), "{:?}: {}", script, message);
+    }
+}
+
+#[test]
+fn test_the_embedded_dialogs_parse() {
+    let dialogs = Dialogs::embedded();
+    for stage in [
+        "torus",
+        "cylinder",
+        "mirror_walls",
+        "klein_bottle",
+        "projective_plane",
+        "prison",
+    ] {
+        assert!(!dialogs.runner(stage).is_finished(), "{}", stage);
+    }
+}
+
+#[test]
+fn test_hover_starts_and_click_advances() {
+    let mut runner = greeting();
+    assert!(!runner.step(IDLE, 1.0));
+    assert_eq!(runner.current_id(), Some("start"));
+    assert!(runner.step(HOVER, 0.1));
+    assert_eq!(runner.current_id(), Some("hello"));
+    // still the same line, so it doesn't appear again
+    assert!(!runner.step(HOVER, 0.1));
+    assert!(runner.step(CLICK, 0.1));
+    assert_eq!(runner.current_id(), Some("bye"));
+    assert_eq!(runner.line_time(), 0.0);
+}
+
+#[test]
+fn test_done_needs_the_whole_duration() {
+    let mut runner = greeting();
+    runner.step(HOVER, 0.1);
+    runner.step(IDLE, 1.0);
+    runner.step(HOVER, 0.5);
+    assert_eq!(runner.current_id(), Some("hello"));
+    runner.step(IDLE, 1.0);
+    // done, but the branch also wants a hover, and the line is no longer visible
+    assert_eq!(runner.current_id(), Some("hello"));
+    assert!(runner.visible_line().is_none());
+    runner.step(HOVER, 0.1);
+    assert_eq!(runner.current_id(), Some("bye"));
+}
+
+#[test]
+fn test_idle_and_after_time_out() {
+    let mut runner = greeting();
+    runner.step(HOVER, 0.1);
+    runner.step(CLICK, 0.1);
+    runner.step(IDLE, 2.0);
+    runner.step(HOVER, 0.1);
+    // hovering resets the idle time
+    runner.step(IDLE, 2.0);
+    assert_eq!(runner.current_id(), Some("bye"));
+    runner.step(IDLE, 1.0);
+    assert!(runner.is_finished());
+    assert!(!runner.step(HOVER, 1.0));
+
+    let mut runner = greeting();
+    runner.step(HOVER, 0.1);
+    runner.step(CLICK, 0.1);
+    for _ in 0..10 {
+        runner.step(HOVER, 1.0);
+    }
+    assert_eq!(runner.current_id(), Some("hello"));
+}
+
+#[test]
+fn test_idle_time_starts_over_with_each_line() {
+    let mut runner = Dialogs::embedded().runner("prison");
+    let mut step_until = |id: &str| {
+        let mut time = 0.0_f32;
+        while runner.current_id() != Some(id) {
+            runner.step(IDLE, 0.1);
+            time += 0.1;
+            assert!(time < 100.0, "never reached {}", id);
+        }
+        time
+    };
+    step_until("give_up");
+    let first = step_until("taunt");
+    assert!((first - 20.0).abs() < 0.2, "{}", first);
+    step_until("give_up");
+    let second = step_until("taunt");
+    assert!((second - 20.0).abs() < 0.2, "{}", second);
+}
@@ -0,0 +1,167 @@
+use macroquad::math::{vec2, Rect, Vec2};
+use press_to_start::forces::{
+    FacingHome, ForceField, ForceFields, HomeAttraction, NoiseField, Probe, Repulsion, Swirl,
+    WallRepulsion,
+};
+
+const REPULSION: Repulsion = Repulsion {
+    range: 100.0,
+    strength: 0.6,
+    falloff: 2.0,
+};
+
+fn probe(pos: Vec2, mouse: Vec2) -> Probe {
+    Probe {
+        pos,
+        mouse,
+        home: vec2(400.0, 300.0),
+        bounds: Rect::new(0.0, 0.0, 800.0, 600.0),
+        time: 0.0,
+    }
+}
+
+fn assert_close(a: Vec2, b: Vec2) {
+    assert!((a - b).length() < 1e-2, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn test_repulsion_pushes_away_within_range() {
+    let pos = vec2(400.0, 300.0);
+    let near = REPULSION.velocity(&probe(pos, pos - vec2(20.0, 0.0)));
+    let far = REPULSION.velocity(&probe(pos, pos - vec2(60.0, 0.0)));
+    assert_close(near, vec2(0.6 * 80.0 * 80.0, 0.0));
+    assert!(far.x > 0.0 && far.x < near.x);
+    assert_eq!(far.y, 0.0);
+    assert_eq!(
+        REPULSION.velocity(&probe(pos, pos - vec2(101.0, 0.0))),
+        Vec2::ZERO
+    );
+    assert_eq!(REPULSION.velocity(&probe(pos, pos)), Vec2::ZERO);
+
+    let linear = Repulsion {
+        falloff: 1.0,
+        ..REPULSION
+    };
+    assert_close(
+        linear.velocity(&probe(pos, pos - vec2(20.0, 0.0))),
+        vec2(0.6 * 80.0, 0.0),
+    );
+}
+
+#[test]
+fn test_swirl_goes_around_the_mouse() {
+    let pos = vec2(400.0, 300.0);
+    for mouse in [vec2(370.0, 300.0), vec2(420.0, 340.0), vec2(400.0, 250.0)] {
+        let swirl = Swirl(REPULSION).velocity(&probe(pos, mouse));
+        let away = REPULSION.velocity(&probe(pos, mouse));
+        assert!(swirl.dot(away).abs() < 1e-2);
+        assert!((swirl.length() - away.length()).abs() < 1e-2);
+    }
+    let beyond = probe(pos, pos + vec2(0.0, 150.0));
+    assert_eq!(Swirl(REPULSION).velocity(&beyond), Vec2::ZERO);
+}
+
+#[test]
+fn test_home_attraction_pulls_home_outside_the_dead_zone() {
+    let home = HomeAttraction {
+        strength: 0.5,
+        dead_zone: 5.0,
+    };
+    let away = probe(vec2(300.0, 200.0), Vec2::ZERO);
+    assert_eq!(home.velocity(&away), vec2(50.0, 50.0));
+    let almost = probe(vec2(397.0, 300.0), Vec2::ZERO);
+    assert_eq!(home.velocity(&almost), Vec2::ZERO);
+}
+
+#[test]
+fn test_walls_push_inwards_near_the_edges() {
+    let walls = WallRepulsion {
+        margin: 40.0,
+        strength: 0.3,
+    };
+    assert_eq!(
+        walls.velocity(&probe(vec2(400.0, 300.0), Vec2::ZERO)),
+        Vec2::ZERO
+    );
+    let top_left = walls.velocity(&probe(vec2(10.0, 20.0), Vec2::ZERO));
+    assert!(top_left.x > top_left.y && top_left.y > 0.0);
+    let bottom_right = walls.velocity(&probe(vec2(790.0, 580.0), Vec2::ZERO));
+    assert_eq!(bottom_right, -top_left);
+}
+
+#[test]
+fn test_noise_is_bounded_smooth_and_seeded() {
+    let at = |seed: u32, time: f32| {
+        let mut probe = probe(Vec2::ZERO, Vec2::ZERO);
+        probe.time = time;
+        NoiseField::new(50.0, 1.0, seed).velocity(&probe)
+    };
+    let samples: Vec<Vec2> = (0..1000).map(|i| at(3, i as f32 * 0.01)).collect();
+    assert!(samples
+        .iter()
+        .all(|v| v.x.abs() <= 50.0 && v.y.abs() <= 50.0));
+    assert!(samples
+        .windows(2)
+        .all(|pair| (pair[0] - pair[1]).length() < 10.0));
+    assert!(samples.iter().any(|v| v.x > 10.0) && samples.iter().any(|v| v.x < -10.0));
+    assert_eq!(at(3, 1.3), at(3, 1.3));
+    assert_ne!(at(3, 1.3), at(4, 1.3));
+}
+
+#[test]
+fn test_facing_home_splits_a_field() {
+    let across = FacingHome {
+        field: REPULSION,
+        along: false,
+    };
+    let along = FacingHome {
+        field: REPULSION,
+        along: true,
+    };
+    let pos = vec2(300.0, 300.0);
+    let in_the_way = probe(pos, pos + vec2(20.0, 0.0));
+    assert_eq!(across.velocity(&in_the_way), Vec2::ZERO);
+    assert_eq!(along.velocity(&in_the_way), REPULSION.velocity(&in_the_way));
+    let to_the_side = probe(pos, pos + vec2(0.0, 20.0));
+    assert_eq!(
+        across.velocity(&to_the_side),
+        REPULSION.velocity(&to_the_side)
+    );
+    assert_eq!(along.velocity(&to_the_side), Vec2::ZERO);
+}
+
+#[test]
+fn test_stages_add_up_their_fields() {
+    let forces = ForceFields::parse(
+        "[a]\nrepulsion 100 0.6 2\n\n[b]\nrepulsion 100 0.6 2\nacross repulsion 100 0.6 2\n",
+    )
+    .unwrap();
+    // to the side of the way home, so `across` applies fully
+    let sample = probe(vec2(400.0, 200.0), vec2(420.0, 200.0));
+    let one = forces.velocity("a", &sample);
+    assert_eq!(one, REPULSION.velocity(&sample));
+    assert_close(forces.velocity("b", &sample), one * 2.0);
+    assert_eq!(forces.velocity("missing", &sample), Vec2::ZERO);
+}
+
+#[test]
+fn test_embedded_forces_cover_the_stages() {
+    let forces = ForceFields::embedded();
+    for stage in [
+        "torus",
+        "klein_bottle",
+        "projective_plane",
+        "rockets",
+        "prison",
+    ] {
+        assert!(forces.get(stage).is_some(), "{}", stage);
+    }
+}
+
+#[test]
+fn test_invalid_forces_are_rejected() {
+    assert!(ForceFields::parse("repulsion 100 0.6 2").is_err());
+    assert!(ForceFields::parse("[a]\ngravity 1").is_err());
+    assert!(ForceFields::parse("[a]\nrepulsion 100 0.6").is_err());
+    assert!(ForceFields::parse("[a]\nhome fast 3").is_err());
+}
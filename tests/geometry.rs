@@ -0,0 +1,69 @@
+use macroquad::math::{vec2, Rect, Vec2};
+use press_to_start::geometry::{overlaps, swept_overlaps, Shape};
+
+fn square(x: f32, y: f32, size: f32) -> Shape {
+    Shape::rect(Rect::new(x, y, size, size))
+}
+
+#[test]
+fn test_polygons() {
+    let a = square(0.0, 0.0, 10.0);
+    assert!(overlaps(&a, &square(5.0, 5.0, 10.0)));
+    assert!(overlaps(&a, &square(10.0, 0.0, 10.0)));
+    assert!(!overlaps(&a, &square(10.5, 0.0, 10.0)));
+    // the bounding boxes overlap, but only the diagonal edge of the triangle separates them
+    let triangle = Shape::triangle(vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(0.0, 10.0));
+    assert!(!overlaps(&triangle, &square(6.0, 6.0, 3.0)));
+    assert!(overlaps(&triangle, &square(4.0, 4.0, 3.0)));
+}
+
+#[test]
+fn test_circles() {
+    let circle = Shape::circle(Vec2::ZERO, 5.0);
+    assert!(overlaps(&circle, &Shape::circle(vec2(9.0, 0.0), 5.0)));
+    assert!(!overlaps(&circle, &Shape::circle(vec2(11.0, 0.0), 5.0)));
+    // near the corner of the square, but outside of it
+    assert!(!overlaps(&circle, &square(4.0, 4.0, 10.0)));
+    assert!(overlaps(&circle, &square(3.0, 3.0, 10.0)));
+    assert!(overlaps(&square(3.0, 3.0, 10.0), &circle));
+}
+
+#[test]
+fn test_translated() {
+    let moved = square(0.0, 0.0, 10.0).translated(vec2(100.0, 0.0));
+    assert_eq!(moved, square(100.0, 0.0, 10.0));
+    assert_eq!(moved.project(Vec2::X), (100.0, 110.0));
+}
+
+#[test]
+fn test_swept_catches_tunneling() {
+    let bullet = square(0.0, 0.0, 2.0);
+    let wall = Shape::rect(Rect::new(50.0, -100.0, 1.0, 200.0));
+    let motion = vec2(100.0, 0.0);
+    assert!(!overlaps(&bullet, &wall));
+    assert!(!overlaps(&bullet.translated(motion), &wall));
+    assert!(swept_overlaps(&bullet, motion, &wall));
+    assert!(!swept_overlaps(&bullet, vec2(40.0, 0.0), &wall));
+    let ball = Shape::circle(Vec2::ZERO, 1.0);
+    assert!(swept_overlaps(&ball, motion, &wall));
+}
+
+#[test]
+fn test_swept_misses_beside_the_path() {
+    let ball = Shape::circle(Vec2::ZERO, 1.0);
+    let post = Shape::circle(vec2(50.0, 3.0), 1.0);
+    assert!(!swept_overlaps(&ball, vec2(100.0, 0.0), &post));
+    assert!(swept_overlaps(&ball, vec2(100.0, 2.0), &post));
+    // a diagonal move that passes next to the corner of a square
+    let box_ = square(10.0, 0.0, 10.0);
+    assert!(!swept_overlaps(
+        &square(0.0, 12.0, 2.0),
+        vec2(30.0, 30.0),
+        &box_
+    ));
+    assert!(swept_overlaps(
+        &square(0.0, 0.0, 2.0),
+        vec2(30.0, 5.0),
+        &box_
+    ));
+}
@@ -0,0 +1,137 @@
+use macroquad::math::{vec2, Rect};
+use press_to_start::launcher::{place, LaunchRules, Launcher, SpawnPoint, SpawnRule};
+use press_to_start::steering::RocketKind;
+
+const DT: f32 = 0.1;
+
+fn rules() -> LaunchRules {
+    LaunchRules {
+        spawns: vec![
+            SpawnRule {
+                point: SpawnPoint::Button,
+                from_wave: 1,
+            },
+            SpawnRule {
+                point: SpawnPoint::ScreenEdge,
+                from_wave: 2,
+            },
+        ],
+        cooldown: 1.0,
+        telegraph: 0.5,
+        max_flying: 2,
+    }
+}
+
+/// Steps until a rocket launches, with `flying` rockets alive
+fn seconds_until_launch(launcher: &mut Launcher, flying: usize) -> Option<f32> {
+    let mut time = 0.0;
+    while time < 20.0 {
+        if launcher.step(DT, flying).is_some() {
+            return Some(time);
+        }
+        time += DT;
+    }
+    None
+}
+
+#[test]
+fn test_launches_are_telegraphed() {
+    let mut launcher = Launcher::new(rules());
+    assert!(launcher.step(DT, 0).is_none());
+    assert!(launcher.telegraph().is_none());
+    launcher.request(RocketKind::Homing, 1);
+    assert_eq!(launcher.pending(), 1);
+    assert!(launcher.step(DT, 0).is_none());
+    let telegraph = *launcher.telegraph().unwrap();
+    assert_eq!(telegraph.kind, RocketKind::Homing);
+    assert!(telegraph.progress() < 0.5);
+    let waited = seconds_until_launch(&mut launcher, 0).unwrap();
+    assert!((waited + DT - 0.5).abs() <= DT * 1.5, "{}", waited);
+    assert_eq!(launcher.pending(), 0);
+}
+
+#[test]
+fn test_cooldown_and_max_flying_hold_launches() {
+    let mut launcher = Launcher::new(rules());
+    launcher.request(RocketKind::Homing, 1);
+    launcher.request(RocketKind::Straight, 1);
+    seconds_until_launch(&mut launcher, 0).unwrap();
+    let waited = seconds_until_launch(&mut launcher, 1).unwrap();
+    assert!(waited >= 1.0 + 0.5 - DT * 1.5, "{}", waited);
+
+    launcher.request(RocketKind::Homing, 1);
+    assert!(seconds_until_launch(&mut launcher, 2).is_none());
+    assert_eq!(launcher.pending(), 1);
+    assert!(seconds_until_launch(&mut launcher, 1).is_some());
+}
+
+fn next_launch_point(launcher: &mut Launcher) -> SpawnPoint {
+    for _ in 0..200 {
+        if let Some(launch) = launcher.step(DT, 0) {
+            return launch.point;
+        }
+    }
+    panic!("no launch")
+}
+
+#[test]
+fn test_spawn_points_unlock_by_wave_and_take_turns() {
+    let mut launcher = Launcher::new(rules());
+    let mut points = Vec::new();
+    for wave in [1, 1, 2, 2] {
+        launcher.request(RocketKind::Homing, wave);
+        points.push(next_launch_point(&mut launcher));
+    }
+    use SpawnPoint::*;
+    assert_eq!(points, vec![Button, Button, Button, ScreenEdge]);
+}
+
+#[test]
+fn test_spawns_start_clear_and_head_inwards() {
+    let screen = vec2(800.0, 600.0);
+    let button = Rect::new(350.0, 280.0, 100.0, 40.0);
+    for turn in 0..6 {
+        let (pos, dir) = place(SpawnPoint::Button, turn, button, screen, 30.0);
+        assert!(!button.contains(pos));
+        assert!(dir.y < 0.0);
+
+        let (pos, dir) = place(SpawnPoint::ScreenEdge, turn, button, screen, 30.0);
+        assert!(dir.dot(screen * 0.5 - pos) > 0.0);
+        assert!(Rect::new(0.0, 0.0, screen.x, screen.y).contains(pos));
+    }
+    let (pos, dir) = place(SpawnPoint::Turret(vec2(0.1, 0.9)), 0, button, screen, 0.0);
+    assert_eq!(pos, vec2(80.0, 540.0));
+    assert!(dir.dot(button.center() - pos) > 0.99 * (button.center() - pos).length());
+}
+
+#[test]
+fn test_rules_are_parsed_from_text() {
+    let text = "# comment\n\ncooldown 1\ntelegraph 0.5\nmax_flying 2\n\
+                spawn 1 button\nspawn 2 edge\n";
+    assert_eq!(LaunchRules::parse(text).unwrap(), rules());
+
+    let embedded = LaunchRules::embedded();
+    assert!(embedded.spawns.iter().any(|rule| rule.from_wave == 1));
+    assert_eq!(embedded.turrets(5).count(), 2);
+    assert_eq!(embedded.turrets(3).count(), 0);
+}
+
+#[test]
+fn test_malformed_rules_are_errors_with_the_line() {
+    for (text, error) in [
+        ("cooldown soon\n", "line 1: soon: "),
+        (
+            "cooldown 1\nspawn 1 window\n",
+            "line 2: unknown spawn point window",
+        ),
+        (
+            "cooldown 1\nspawn 1 turret 0.5\n",
+            "line 2: unknown spawn point turret 0.5",
+        ),
+        ("cooldown 1\nrockets 3\n", "line 2: unknown rule rockets 3"),
+        ("cooldown 1\nmax_flying 2\n", "missing telegraph"),
+    ] {
+        let message = LaunchRules::parse(text).err().unwrap_or_default();
+        assert!(message.starts_with(error), "{:?}: {}", text, message);
+    }
+}
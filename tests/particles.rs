@@ -0,0 +1,58 @@
+use macroquad::color::{Color, BLACK, WHITE};
+use macroquad::math::{vec2, Vec2};
+use press_to_start::particles::{color_ramp, Emitter, Particles, EXPLOSION};
+
+const RAMP: &[Color] = &[WHITE, BLACK];
+
+const BURST: Emitter = Emitter {
+    count: 10,
+    speed: (100.0, 100.0),
+    spread: 0.0,
+    lifetime: (1.0, 2.0),
+    radius: 1.0,
+    drag: 0.0,
+    colors: RAMP,
+};
+
+#[test]
+fn test_particles_move_and_expire() {
+    let mut particles = Particles::new(100, 1);
+    particles.emit(&BURST, Vec2::ZERO, vec2(0.0, 1.0));
+    assert_eq!(particles.particles().len(), 10);
+    particles.step(0.5);
+    for particle in particles.particles() {
+        assert!((particle.pos - vec2(0.0, 50.0)).length() < 0.001);
+    }
+    particles.step(1.0);
+    let alive = particles.particles();
+    assert!(alive.iter().all(|p| p.lifetime > 1.5));
+    particles.step(0.5);
+    assert!(particles.is_empty());
+}
+
+#[test]
+fn test_pool_does_not_grow() {
+    let mut particles = Particles::new(150, 1);
+    particles.emit(&EXPLOSION, Vec2::ZERO, Vec2::X);
+    particles.emit(&EXPLOSION, Vec2::ZERO, Vec2::X);
+    assert_eq!(particles.particles().len(), 150);
+}
+
+#[test]
+fn test_emission_is_deterministic() {
+    let mut first = Particles::new(200, 7);
+    let mut second = Particles::new(200, 7);
+    first.emit(&EXPLOSION, Vec2::ZERO, Vec2::X);
+    second.emit(&EXPLOSION, Vec2::ZERO, Vec2::X);
+    assert_eq!(first.particles(), second.particles());
+}
+
+#[test]
+fn test_color_ramp() {
+    assert_eq!(color_ramp(RAMP, 0.0), WHITE);
+    assert_eq!(color_ramp(RAMP, 1.0), BLACK);
+    assert_eq!(color_ramp(RAMP, 2.0), BLACK);
+    let middle = color_ramp(&[WHITE, BLACK, WHITE], 0.25);
+    assert!((middle.r - 0.5).abs() < 0.001);
+    assert_eq!(color_ramp(&[WHITE], 0.5), WHITE);
+}
@@ -0,0 +1,351 @@
+use juquad::widgets::anchor::Anchor;
+use juquad::widgets::Widget;
+use macroquad::input::KeyCode;
+use macroquad::math::{vec2, Vec2};
+use press_to_start::achievements::Achievement;
+use press_to_start::audio::SoundEffect;
+use press_to_start::bot::BotAction::*;
+use press_to_start::bot::{run_bot, Bot};
+use press_to_start::measure_text_headless;
+use press_to_start::menus::column::menu_top;
+use press_to_start::stages::driver::{
+    Shared, Simulation, StageGraph, StageId, StageOutcome, Transition,
+};
+use press_to_start::timestep::FIXED_DT;
+
+const SCREEN: Vec2 = vec2(800.0, 600.0);
+const MAX_STEPS: usize = 10_000;
+
+fn seconds(seconds: f64) -> usize {
+    (seconds / FIXED_DT as f64).ceil() as usize
+}
+
+fn new_simulation(start: StageId, bot: &Bot) -> Simulation {
+    let mut graph = StageGraph::campaign();
+    graph.start = start;
+    let shared = Shared::new(SCREEN, measure_text_headless, &bot.input());
+    Simulation::new(graph, shared)
+}
+
+fn button_height(simulation: &Simulation, bot: &Bot) -> f32 {
+    let shared = &simulation.shared;
+    let anchor = Anchor::center_v(Vec2::ZERO);
+    shared.new_button("", anchor, &bot.input()).rect().h
+}
+
+/// The center of the button in that row of a menu
+fn menu_row(simulation: &Simulation, bot: &Bot, row: usize) -> Vec2 {
+    let height = button_height(simulation, bot);
+    menu_top(SCREEN) + vec2(0.0, height * (row as f32 + 0.5))
+}
+
+const RESUME: usize = 0;
+const RESTART_STAGE: usize = 1;
+const QUIT_TO_DESKTOP: usize = 3;
+
+#[test]
+fn test_torus_dialogs_complete() {
+    let mut bot = Bot::new(
+        vec2(100.0, 100.0),
+        vec![
+            ChaseButton { speed: 1000.0 },
+            Wait(seconds(5.0) + 10),
+            ChaseButton { speed: 1000.0 },
+            Wait(seconds(5.0) + 10),
+        ],
+    );
+    let mut simulation = new_simulation(StageId::Torus, &bot);
+    run_bot(&mut simulation, &mut bot, MAX_STEPS);
+    assert_eq!(
+        simulation.history[0],
+        (StageId::Torus, Some(StageOutcome::Next))
+    );
+    assert_eq!(simulation.current(), Some(StageId::Cylinder));
+    assert!(simulation.shared.save.data().is_unlocked("small_talk"));
+    assert_eq!(
+        simulation.shared.toasts.current(),
+        Some(Achievement::SmallTalk)
+    );
+}
+
+#[test]
+fn test_walled_topologies_lead_to_the_flipped_ones() {
+    let mut bot = Bot::new(
+        vec2(100.0, 100.0),
+        vec![
+            ChaseButton { speed: 1000.0 },
+            Wait(seconds(5.0) + 10),
+            ChaseButton { speed: 1000.0 },
+            Wait(seconds(5.0) + 10),
+        ],
+    );
+    let mut simulation = new_simulation(StageId::Cylinder, &bot);
+    run_bot(&mut simulation, &mut bot, MAX_STEPS);
+    assert_eq!(
+        simulation.history,
+        vec![
+            (StageId::Cylinder, Some(StageOutcome::Next)),
+            (StageId::MirrorWalls, Some(StageOutcome::Next)),
+            (StageId::KleinBottle, None),
+        ]
+    );
+    assert!(!simulation.shared.save.data().is_unlocked("small_talk"));
+}
+
+#[test]
+fn test_reaching_every_wrap_stage_is_an_achievement() {
+    let mut bot = Bot::new(vec2(100.0, 100.0), vec![Wait(1)]);
+    let mut simulation = new_simulation(StageId::ProjectivePlane, &bot);
+    let save = &mut simulation.shared.save;
+    for stage in [StageId::Torus, StageId::Cylinder, StageId::MirrorWalls] {
+        save.reach(stage);
+    }
+    run_bot(&mut simulation, &mut bot, MAX_STEPS);
+    assert!(!simulation.shared.save.data().is_unlocked("cartographer"));
+
+    let mut bot = Bot::new(vec2(100.0, 100.0), vec![Wait(1)]);
+    let mut simulation = new_simulation(StageId::ProjectivePlane, &bot);
+    let save = &mut simulation.shared.save;
+    for stage in [
+        StageId::Torus,
+        StageId::Cylinder,
+        StageId::MirrorWalls,
+        StageId::KleinBottle,
+    ] {
+        save.reach(stage);
+    }
+    run_bot(&mut simulation, &mut bot, MAX_STEPS);
+    assert!(simulation.shared.save.data().is_unlocked("cartographer"));
+    assert_eq!(
+        simulation.shared.toasts.current(),
+        Some(Achievement::Cartographer)
+    );
+}
+
+#[test]
+fn test_rocket_hitting_the_mouse_is_game_over() {
+    let mut bot = Bot::new(vec2(100.0, 100.0), vec![Wait(seconds(10.0))]);
+    let mut simulation = new_simulation(StageId::Rockets, &bot);
+    run_bot(&mut simulation, &mut bot, MAX_STEPS);
+    assert_eq!(
+        simulation.history[0],
+        (StageId::Rockets, Some(StageOutcome::Lost))
+    );
+    assert_eq!(simulation.current(), Some(StageId::GameOver));
+    assert_eq!(simulation.shared.save.data().deaths, 1);
+    assert!(simulation.shared.save.data().has_reached(StageId::GameOver));
+    let queued = simulation.shared.audio.queued();
+    assert!(queued.contains(&SoundEffect::Thrust));
+    assert_eq!(queued.last(), Some(&SoundEffect::DeathSting));
+}
+
+#[test]
+fn test_menus_freeze_the_stage() {
+    let mut bot = Bot::new(
+        vec2(100.0, 100.0),
+        vec![Press(KeyCode::F1), Wait(seconds(10.0))],
+    );
+    let mut simulation = new_simulation(StageId::Rockets, &bot);
+    run_bot(&mut simulation, &mut bot, MAX_STEPS);
+    assert_eq!(simulation.history, vec![(StageId::Rockets, None)]);
+}
+
+#[test]
+fn test_clicking_restart_after_game_over() {
+    let mut bot = Bot::new(
+        vec2(100.0, 100.0),
+        vec![Wait(seconds(10.0)), MoveTo(SCREEN * 0.5), Click, Wait(1)],
+    );
+    let mut simulation = new_simulation(StageId::Rockets, &bot);
+    run_bot(&mut simulation, &mut bot, MAX_STEPS);
+    assert_eq!(
+        simulation.history[1],
+        (StageId::GameOver, Some(StageOutcome::Restart))
+    );
+    assert_eq!(simulation.current(), Some(StageId::Rockets));
+}
+
+#[test]
+fn test_pausing_freezes_the_stage_until_resumed() {
+    let bot = Bot::new(vec2(100.0, 100.0), vec![]);
+    let simulation = new_simulation(StageId::Rockets, &bot);
+    let resume = menu_row(&simulation, &bot, RESUME);
+    let mut bot = Bot::new(
+        vec2(100.0, 100.0),
+        vec![
+            Press(KeyCode::Escape),
+            Wait(seconds(10.0)),
+            MoveTo(resume),
+            Click,
+            Wait(seconds(10.0)),
+        ],
+    );
+    let mut simulation = new_simulation(StageId::Rockets, &bot);
+    run_bot(&mut simulation, &mut bot, seconds(10.0));
+    assert!(simulation.is_paused());
+    assert_eq!(simulation.history, vec![(StageId::Rockets, None)]);
+    run_bot(&mut simulation, &mut bot, MAX_STEPS);
+    assert!(!simulation.is_paused());
+    assert_eq!(
+        simulation.history[0],
+        (StageId::Rockets, Some(StageOutcome::Lost))
+    );
+}
+
+#[test]
+fn test_restarting_the_stage_from_the_pause_menu() {
+    let bot = Bot::new(vec2(100.0, 100.0), vec![]);
+    let simulation = new_simulation(StageId::Torus, &bot);
+    let restart = menu_row(&simulation, &bot, RESTART_STAGE);
+    let mut bot = Bot::new(
+        vec2(100.0, 100.0),
+        vec![Press(KeyCode::P), Wait(1), MoveTo(restart), Click, Wait(1)],
+    );
+    let mut simulation = new_simulation(StageId::Torus, &bot);
+    run_bot(&mut simulation, &mut bot, MAX_STEPS);
+    assert_eq!(
+        simulation.history,
+        vec![
+            (StageId::Torus, Some(StageOutcome::Restart)),
+            (StageId::Torus, None),
+        ]
+    );
+    assert!(!simulation.is_paused());
+}
+
+#[test]
+fn test_quitting_the_torus_exits() {
+    let bot = Bot::new(vec2(100.0, 100.0), vec![]);
+    let simulation = new_simulation(StageId::Torus, &bot);
+    let quit = menu_row(&simulation, &bot, QUIT_TO_DESKTOP);
+    let mut bot = Bot::new(
+        vec2(100.0, 100.0),
+        vec![Press(KeyCode::Escape), Wait(1), MoveTo(quit), Click],
+    );
+    let mut simulation = new_simulation(StageId::Torus, &bot);
+    run_bot(&mut simulation, &mut bot, MAX_STEPS);
+    assert_eq!(
+        simulation.history,
+        vec![(StageId::Torus, Some(StageOutcome::Quit))]
+    );
+    assert!(simulation.is_finished());
+}
+
+#[test]
+fn test_giving_up_in_prison_exits() {
+    let bot = Bot::new(vec2(100.0, 100.0), vec![]);
+    let simulation = new_simulation(StageId::Rockets, &bot);
+    let quit = menu_row(&simulation, &bot, QUIT_TO_DESKTOP);
+    let exit = SCREEN * 0.5 + vec2(0.0, button_height(&simulation, &bot));
+    let mut bot = Bot::new(
+        vec2(100.0, 500.0),
+        vec![
+            Press(KeyCode::Escape),
+            Wait(1),
+            MoveTo(quit),
+            Click,
+            Wait(seconds(6.25) + 10),
+            MoveTo(SCREEN * 0.5),
+            Click,
+            Wait(seconds(2.0)),
+            MoveTo(exit),
+            Click,
+            Wait(1),
+        ],
+    );
+    let mut simulation = new_simulation(StageId::Rockets, &bot);
+    run_bot(&mut simulation, &mut bot, MAX_STEPS);
+    assert_eq!(
+        simulation.history,
+        vec![
+            (StageId::Rockets, Some(StageOutcome::TriedToQuit)),
+            (StageId::Prison, Some(StageOutcome::GaveUp)),
+        ]
+    );
+    assert!(simulation.is_finished());
+    assert!(simulation.shared.save.data().is_unlocked("surrender"));
+}
+
+#[test]
+fn test_quitting_the_prison_keeps_the_player_inside() {
+    let bot = Bot::new(vec2(100.0, 100.0), vec![]);
+    let simulation = new_simulation(StageId::Prison, &bot);
+    let quit = menu_row(&simulation, &bot, QUIT_TO_DESKTOP);
+    let mut bot = Bot::new(
+        vec2(100.0, 500.0),
+        vec![
+            Press(KeyCode::Escape),
+            Wait(1),
+            MoveTo(quit),
+            Click,
+            Wait(1),
+        ],
+    );
+    let mut simulation = new_simulation(StageId::Prison, &bot);
+    run_bot(&mut simulation, &mut bot, MAX_STEPS);
+    assert_eq!(
+        simulation.history,
+        vec![
+            (StageId::Prison, Some(StageOutcome::TriedToQuit)),
+            (StageId::Prison, None),
+        ]
+    );
+    assert!(!simulation.is_paused());
+    assert!(simulation.cursor_grabbed());
+}
+
+#[test]
+fn test_quitting_after_giving_up_exits() {
+    let bot = Bot::new(vec2(100.0, 100.0), vec![]);
+    let simulation = new_simulation(StageId::Prison, &bot);
+    let quit = menu_row(&simulation, &bot, QUIT_TO_DESKTOP);
+    let mut bot = Bot::new(
+        vec2(100.0, 500.0),
+        vec![
+            Wait(seconds(6.25) + 10),
+            MoveTo(SCREEN * 0.5),
+            Click,
+            Wait(seconds(2.0)),
+            Press(KeyCode::Escape),
+            Wait(1),
+            MoveTo(quit),
+            Click,
+            Wait(1),
+        ],
+    );
+    let mut simulation = new_simulation(StageId::Prison, &bot);
+    run_bot(&mut simulation, &mut bot, MAX_STEPS);
+    assert_eq!(
+        simulation.history,
+        vec![(StageId::Prison, Some(StageOutcome::Quit))]
+    );
+    assert!(simulation.is_finished());
+}
+
+#[test]
+fn test_end_screens_can_not_route_quitting() {
+    let graph = StageGraph::campaign().with(
+        StageId::GameOver,
+        StageOutcome::Quit,
+        Transition::To(StageId::Torus),
+    );
+    assert_eq!(
+        graph.next(StageId::GameOver, StageOutcome::Quit),
+        Transition::Exit
+    );
+    assert!(graph.traps(StageId::Rockets));
+    assert!(!graph.traps(StageId::Torus));
+}
+
+#[test]
+fn test_switching_language_resizes_the_button() {
+    let mut bot = Bot::new(vec2(100.0, 100.0), vec![Press(KeyCode::L), Wait(1)]);
+    let mut simulation = new_simulation(StageId::Torus, &bot);
+    simulation.step(&bot.input(), FIXED_DT);
+    let english = simulation.shared.button.rect();
+    run_bot(&mut simulation, &mut bot, MAX_STEPS);
+    assert_eq!(simulation.shared.strings.language(), "es");
+    let spanish = simulation.shared.button.rect();
+    assert!(spanish.w > english.w);
+    assert!((spanish.center() - english.center()).length() < 1.0);
+}
@@ -0,0 +1,146 @@
+use juquad::widgets::Widget;
+use macroquad::input::{KeyCode, MouseButton};
+use macroquad::math::{vec2, Vec2};
+use press_to_start::actions::{Action, Binding, Bindings};
+use press_to_start::bot::Bot;
+use press_to_start::bot::BotAction::*;
+use press_to_start::measure_text_headless;
+use press_to_start::replay::{InputFrame, InputRecorder, Recording, Replayer};
+use press_to_start::save::{FileBackend, Save};
+use press_to_start::settings::{Difficulty, Settings};
+use press_to_start::stages::driver::{Shared, Simulation, StageGraph, StageId};
+use press_to_start::timestep::FIXED_DT;
+
+const SCREEN: Vec2 = vec2(800.0, 600.0);
+
+fn frame(mouse: Vec2) -> InputFrame {
+    InputFrame {
+        mouse,
+        screen: SCREEN,
+        ..Default::default()
+    }
+}
+
+fn tuned_settings() -> (Settings, Bindings) {
+    let settings = Settings {
+        language: Some("es".to_string()),
+        difficulty: Difficulty::Hard,
+        reduced_motion: true,
+        prison_sensitivity: 0.5,
+        ..Default::default()
+    };
+    let mut bindings = Bindings::default();
+    bindings
+        .rebind(Action::Pause, Binding::Mouse(MouseButton::Right))
+        .unwrap();
+    (settings, bindings)
+}
+
+fn new_simulation(start: StageId, input: &Bot) -> Simulation {
+    let mut graph = StageGraph::campaign();
+    graph.start = start;
+    let shared = Shared::new(SCREEN, measure_text_headless, &input.input());
+    Simulation::new(graph, shared)
+}
+
+#[test]
+fn test_recording_round_trip() {
+    let (settings, bindings) = tuned_settings();
+    let mut clicking = frame(vec2(10.5, 20.0));
+    clicking.buttons_down = 1;
+    clicking.buttons_pressed = 1;
+    clicking.keys_pressed = vec![KeyCode::Escape, KeyCode::F3];
+    let recording = Recording {
+        settings,
+        bindings,
+        frames: vec![
+            frame(Vec2::ZERO),
+            frame(Vec2::ZERO),
+            frame(Vec2::ZERO),
+            clicking,
+            frame(vec2(10.5, 20.0)),
+        ],
+    };
+    let text = recording.serialize();
+    assert!(text.contains("\n*2\n"));
+    assert!(text.contains("settings difficulty = hard\n"));
+    assert!(text.contains("bindings pause = MouseRight\n"));
+    assert_eq!(Recording::parse(&text), Ok(recording));
+}
+
+#[test]
+fn test_repetitions_copy_the_frame_above() {
+    let header = Recording::default().serialize();
+    let text = format!("{}1 2 0 0 0 0 0 800 600 - -\n*3\n", header);
+    let recording = Recording::parse(&text).unwrap();
+    assert_eq!(recording.frames, vec![frame(vec2(1.0, 2.0)); 4]);
+
+    assert!(Recording::parse(&format!("{}*3\n", header)).is_err());
+    assert!(Recording::parse("press-to-start-replay 0\n").is_err());
+    assert!(Recording::parse(&format!("{}1 2 0\n", header)).is_err());
+    assert!(Recording::parse(&format!("{}settings difficulty = easyish\n", header)).is_err());
+}
+
+#[test]
+fn test_replay_plays_like_the_recorded_session() {
+    let mut bot = Bot::new(
+        vec2(100.0, 100.0),
+        vec![
+            ChaseButton { speed: 10.0 },
+            Click,
+            Wait(30),
+            MoveTo(vec2(300.0, 200.0)),
+            Press(KeyCode::F3),
+            Wait(30),
+        ],
+    );
+    let (settings, bindings) = tuned_settings();
+    let recorder = InputRecorder::new(settings.clone(), bindings.clone());
+    let mut live = new_simulation(StageId::Rockets, &bot);
+    live.shared.settings = settings;
+    live.shared.bindings = bindings;
+    live.shared.set_language("es", &bot.input());
+    while !bot.is_done() {
+        bot.think(&live.shared);
+        let input = recorder.record_step(&bot.input(), SCREEN);
+        assert!(live.step(&input, FIXED_DT));
+    }
+
+    let recording = Recording::parse(&recorder.recording().serialize()).unwrap();
+    let idle = Bot::new(Vec2::ZERO, vec![]);
+    let mut replayed = new_simulation(StageId::Rockets, &idle);
+    recording.restore(&mut replayed.shared, &idle.input());
+    let mut replayer = Replayer::new(recording);
+    while let Some((input, screen)) = replayer.next_step() {
+        replayed.shared.screen = screen;
+        assert!(replayed.step(&input, FIXED_DT));
+    }
+    assert_eq!(replayed.history, live.history);
+    assert_eq!(replayed.shared.button.rect(), live.shared.button.rect());
+    assert_eq!(replayed.shared.settings, live.shared.settings);
+    assert_eq!(replayed.shared.strings.language(), "es");
+    assert!(replayed.shared.dev_overlay);
+}
+
+#[test]
+fn test_replay_does_not_touch_the_files_of_this_session() {
+    let dir = std::env::temp_dir().join(format!("press-to-start-replay-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+    let idle = Bot::new(Vec2::ZERO, vec![]);
+    let mut simulation = new_simulation(StageId::Rockets, &idle);
+    let shared = &mut simulation.shared;
+    shared.save = Save::load(Box::new(FileBackend::new(&path("save.txt"))));
+    shared.settings_backend = Box::new(FileBackend::new(&path("settings.txt")));
+    shared.bindings_backend = Box::new(FileBackend::new(&path("bindings.txt")));
+
+    Recording::default().restore(shared, &idle.input());
+    shared.save.update(|data| data.deaths += 1);
+    shared.save_settings();
+    shared.save_bindings();
+    assert_eq!(shared.save.data().deaths, 1);
+    for name in ["save.txt", "settings.txt", "bindings.txt"] {
+        assert!(!dir.join(name).exists(), "{} was written", name);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
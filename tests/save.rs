@@ -0,0 +1,57 @@
+use press_to_start::save::{MemoryBackend, Save, SaveBackend, SaveData, SAVE_VERSION};
+use press_to_start::stages::driver::StageId;
+
+#[test]
+fn test_save_round_trip() {
+    let mut data = SaveData::default();
+    data.reached.insert(StageId::Torus);
+    data.reached.insert(StageId::Prison);
+    data.deaths = 3;
+    data.unlocks.insert("something".to_string());
+    assert_eq!(SaveData::parse(&data.serialize()), Ok(data));
+}
+
+#[test]
+fn test_empty_save_round_trip() {
+    let data = SaveData::default();
+    assert_eq!(SaveData::parse(&data.serialize()), Ok(data));
+}
+
+#[test]
+fn test_newer_versions_are_rejected() {
+    let text = format!("press-to-start-save {}\ndeaths 1\n", SAVE_VERSION + 1);
+    assert!(SaveData::parse(&text).is_err());
+}
+
+#[test]
+fn test_missing_fields_are_zero() {
+    let data = SaveData::parse("press-to-start-save 1\ndeaths 2\n").unwrap();
+    assert_eq!(data.deaths, 2);
+    assert_eq!(data.gave_up, 0);
+    assert!(data.reached.is_empty());
+}
+
+#[test]
+fn test_changes_survive_reloading() {
+    let backend = MemoryBackend::new();
+    let mut save = Save::load(Box::new(backend.clone()));
+    save.reach(StageId::Rockets);
+    save.update(|data| data.deaths += 1);
+    assert!(save.unlock("something"));
+    assert!(!save.unlock("something"));
+
+    let reloaded = Save::load(Box::new(backend));
+    assert!(reloaded.data().has_reached(StageId::Rockets));
+    assert_eq!(reloaded.data().deaths, 1);
+    assert!(reloaded.data().is_unlocked("something"));
+}
+
+#[test]
+fn test_unreadable_saves_are_not_overwritten() {
+    let mut backend = MemoryBackend::new();
+    let newer = format!("press-to-start-save {}\n", SAVE_VERSION + 1);
+    backend.write(&newer).unwrap();
+    let mut save = Save::load(Box::new(backend.clone()));
+    save.update(|data| data.deaths += 1);
+    assert_eq!(backend.text(), Some(newer));
+}
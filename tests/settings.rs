@@ -0,0 +1,29 @@
+use press_to_start::audio::Volumes;
+use press_to_start::settings::{Difficulty, Settings, WindowMode};
+
+#[test]
+fn test_settings_round_trip() {
+    let settings = Settings {
+        language: Some("es".to_string()),
+        difficulty: Difficulty::Hard,
+        reduced_motion: true,
+        prison_sensitivity: 0.75,
+        window_mode: WindowMode::Fullscreen,
+        volumes: Volumes {
+            master: 0.75,
+            effects: 0.5,
+            dialog: 0.0,
+        },
+    };
+    assert_eq!(Settings::parse(&settings.serialize()), Ok(settings));
+    let settings = Settings::default();
+    assert_eq!(Settings::parse(&settings.serialize()), Ok(settings));
+}
+
+#[test]
+fn test_invalid_settings_are_rejected() {
+    assert!(Settings::parse("difficulty = impossible\n").is_err());
+    assert!(Settings::parse("prison_sensitivity = -1\n").is_err());
+    assert!(Settings::parse("master_volume = 11\n").is_err());
+    assert!(Settings::parse("volume = 1\n").is_err());
+}
@@ -0,0 +1,78 @@
+use macroquad::math::{vec2, Vec2};
+use press_to_start::steering::{RocketKind, ALL_ROCKET_KINDS};
+
+const DT: f32 = 1.0 / 60.0;
+
+/// Steers for a second toward a mouse to the right, with the button far below
+fn heading_after_a_second(kind: RocketKind) -> Vec2 {
+    let steering = kind.params().steering;
+    let (mut pos, mut heading) = (Vec2::ZERO, vec2(0.0, -1.0));
+    for _ in 0..60 {
+        heading = steering.steer(heading, pos, vec2(1000.0, 0.0), vec2(0.0, 1000.0), 1.0, DT);
+        pos += heading * 10.0;
+    }
+    heading
+}
+
+#[test]
+fn test_homing_turns_toward_the_mouse() {
+    let heading = heading_after_a_second(RocketKind::Homing);
+    assert!(heading.x > 0.5, "{:?}", heading);
+    assert!((heading.length() - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_straight_never_turns() {
+    assert_eq!(
+        heading_after_a_second(RocketKind::Straight),
+        vec2(0.0, -1.0)
+    );
+    assert!(RocketKind::Straight.params().aims_at_launch);
+}
+
+#[test]
+fn test_relentless_is_slow_but_turns_harder_and_lasts() {
+    let homing = RocketKind::Homing.params();
+    let relentless = RocketKind::Relentless.params();
+    assert!(relentless.speed < homing.speed);
+    assert!(relentless.steering.to_mouse > homing.steering.to_mouse);
+    assert!(relentless.fuel > homing.fuel);
+}
+
+#[test]
+fn test_sine_wave_swings_around_its_heading() {
+    let steering = RocketKind::SineWave.params().steering;
+    let heading = vec2(1.0, 0.0);
+    let swings: Vec<f32> = (0..60)
+        .map(|frame| steering.direction(heading, frame as f32 * DT).y)
+        .collect();
+    assert!(swings.iter().any(|y| *y > 0.3));
+    assert!(swings.iter().any(|y| *y < -0.3));
+    let still = RocketKind::Homing.params().steering;
+    assert_eq!(still.direction(heading, 0.3), heading);
+}
+
+#[test]
+fn test_splitting_fans_out_around_the_heading() {
+    let split = RocketKind::Splitting.params().split.unwrap();
+    let headings = split.headings(vec2(0.0, -1.0));
+    assert_eq!(headings.len(), split.into);
+    let sum = headings
+        .iter()
+        .fold(Vec2::ZERO, |sum, heading| sum + *heading);
+    assert!(sum.x.abs() < 1e-4);
+    assert!(headings.windows(2).all(|pair| pair[0] != pair[1]));
+    assert!(split.child.params().split.is_none());
+}
+
+#[test]
+fn test_only_decoys_are_harmless() {
+    for kind in ALL_ROCKET_KINDS {
+        assert_eq!(
+            kind.params().harmful,
+            kind != RocketKind::Decoy,
+            "{:?}",
+            kind
+        );
+    }
+}
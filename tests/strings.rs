@@ -0,0 +1,84 @@
+use press_to_start::strings::{parse_table, Strings, FALLBACK_LANGUAGE};
+use std::collections::HashMap;
+
+fn table(text: &str) -> HashMap<String, String> {
+    parse_table(text).unwrap()
+}
+
+fn strings() -> Strings {
+    let mut strings = Strings::embedded();
+    strings.add_language("en", table("greeting = Hello\nfarewell = Bye\n"));
+    strings.add_language("xx", table("# only one\ngreeting = Xello\n"));
+    strings
+}
+
+#[test]
+fn test_looks_up_the_current_language() {
+    let mut strings = strings();
+    assert_eq!(strings.language(), FALLBACK_LANGUAGE);
+    assert_eq!(strings.get("greeting"), "Hello");
+    assert!(strings.set_language("xx"));
+    assert_eq!(strings.get("greeting"), "Xello");
+}
+
+#[test]
+fn test_missing_translations_fall_back_to_the_default_language() {
+    let mut strings = strings();
+    strings.set_language("xx");
+    assert_eq!(strings.get("farewell"), "Bye");
+    assert_eq!(strings.get_or("farewell", "default"), "Bye");
+}
+
+#[test]
+fn test_missing_keys_show_the_key_or_the_default() {
+    let mut strings = strings();
+    strings.set_language("xx");
+    assert_eq!(strings.get("nowhere"), "nowhere");
+    assert_eq!(strings.get_or("nowhere", "default"), "default");
+}
+
+#[test]
+fn test_unknown_languages_keep_the_current_one() {
+    let mut strings = strings();
+    strings.set_language("es");
+    assert!(!strings.set_language("zz"));
+    assert_eq!(strings.language(), "es");
+}
+
+#[test]
+fn test_next_language_cycles_through_all_of_them() {
+    let mut strings = strings();
+    assert_eq!(strings.languages(), ["en", "es", "xx"]);
+    let mut seen = Vec::new();
+    for _ in 0..3 {
+        let next = strings.next_language().to_string();
+        strings.set_language(&next);
+        seen.push(next);
+    }
+    assert_eq!(seen, ["es", "xx", "en"]);
+}
+
+#[test]
+fn test_tables_need_an_equals_sign() {
+    assert_eq!(
+        parse_table("a = 1\n\nnot a pair\n"),
+        Err("line 3: expected `key = text`".to_string())
+    );
+    assert_eq!(table("  a =  spaced out  ")["a"], "spaced out");
+}
+
+#[test]
+fn test_embedded_languages_translate_the_same_keys() {
+    let mut strings = Strings::embedded();
+    let english = table(include_str!("../assets/lang/en.txt"));
+    let spanish = table(include_str!("../assets/lang/es.txt"));
+    let mut missing = english
+        .keys()
+        .filter(|key| !spanish.contains_key(*key))
+        .collect::<Vec<_>>();
+    missing.sort();
+    assert!(missing.is_empty(), "missing in es: {:?}", missing);
+    assert_eq!(english.len(), spanish.len());
+    strings.set_language("es");
+    assert_eq!(strings.get("action.dev_overlay"), "Capa de desarrollo");
+}
@@ -0,0 +1,75 @@
+use juquad::input::input_trait::InputTrait;
+use macroquad::input::{KeyCode, MouseButton};
+use macroquad::math::vec2;
+use press_to_start::replay::{button_bit, FrameInput, InputFrame};
+use press_to_start::timestep::{has_events, FixedTimestep, HeldInput, FIXED_DT};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn test_steps_accumulate_across_frames() {
+    let mut timestep = FixedTimestep::new();
+    assert_eq!(timestep.advance(FIXED_DT * 0.6, false), 0);
+    assert_eq!(timestep.advance(FIXED_DT * 0.6, false), 1);
+    assert_eq!(timestep.advance(FIXED_DT * 2.0, false), 2);
+    let steps = (0..60)
+        .map(|_| timestep.advance(1.0 / 144.0, false))
+        .sum::<u32>();
+    assert!((25..=27).contains(&steps), "{}", steps);
+}
+
+#[test]
+fn test_long_frames_drop_time_instead_of_catching_up() {
+    let mut timestep = FixedTimestep::new();
+    assert_eq!(timestep.advance(1.0, false), 8);
+    // only one step of the rest of that second is kept
+    assert_eq!(timestep.advance(0.0, false), 1);
+    assert_eq!(timestep.advance(0.0, false), 0);
+}
+
+#[test]
+fn test_events_borrow_a_step_from_the_next_frame() {
+    let mut timestep = FixedTimestep::new();
+    assert_eq!(timestep.advance(FIXED_DT * 0.25, true), 1);
+    // that step was paid for with the time of the next ones
+    for _ in 0..3 {
+        assert_eq!(timestep.advance(FIXED_DT * 0.5, false), 0);
+    }
+    assert_eq!(timestep.advance(FIXED_DT * 0.5, false), 1);
+}
+
+fn pressing() -> Box<dyn InputTrait> {
+    let left = button_bit(MouseButton::Left);
+    let frame = InputFrame {
+        mouse: vec2(3.0, 4.0),
+        buttons_down: left,
+        buttons_pressed: left,
+        keys_down: vec![KeyCode::P],
+        keys_pressed: vec![KeyCode::P],
+        ..Default::default()
+    };
+    Box::new(FrameInput::new(Rc::new(RefCell::new(frame))))
+}
+
+#[test]
+fn test_held_input_keeps_what_is_held_but_not_the_events() {
+    let input = pressing();
+    let held: Box<dyn InputTrait> = Box::new(HeldInput::new(&input));
+    for held in [held.clone(), held] {
+        assert_eq!(held.mouse_position(), vec2(3.0, 4.0));
+        assert!(held.is_mouse_button_down(MouseButton::Left));
+        assert!(held.is_key_down(KeyCode::P));
+        assert!(!held.is_mouse_button_pressed(MouseButton::Left));
+        assert!(!held.is_mouse_button_released(MouseButton::Left));
+        assert!(!held.is_key_pressed(KeyCode::P));
+    }
+    assert!(input.is_key_pressed(KeyCode::P));
+}
+
+#[test]
+fn test_clicks_and_keys_are_events() {
+    assert!(has_events(&pressing(), false));
+    let idle: Box<dyn InputTrait> = Box::new(FrameInput::new(Rc::default()));
+    assert!(!has_events(&idle, false));
+    assert!(has_events(&idle, true));
+}
@@ -0,0 +1,132 @@
+use macroquad::math::{vec2, Rect};
+use press_to_start::torus_space::{Topology, TorusSpace};
+
+fn space() -> TorusSpace {
+    TorusSpace::new(vec2(800.0, 600.0))
+}
+
+#[test]
+fn test_rect_inside_has_no_copies() {
+    let rect = Rect::new(100.0, 100.0, 50.0, 20.0);
+    assert!(space().wrapped_copies(rect).is_empty());
+    assert_eq!(space().main_copy(rect), rect);
+}
+
+#[test]
+fn test_copies_through_edges_and_corners() {
+    let left = Rect::new(-10.0, 100.0, 50.0, 20.0);
+    assert_eq!(
+        space().wrapped_copies(left),
+        vec![Rect::new(790.0, 100.0, 50.0, 20.0)]
+    );
+    let bottom = Rect::new(100.0, 590.0, 50.0, 20.0);
+    assert_eq!(
+        space().wrapped_copies(bottom),
+        vec![Rect::new(100.0, -10.0, 50.0, 20.0)]
+    );
+    let top_left = Rect::new(-10.0, -10.0, 50.0, 20.0);
+    let copies = space().wrapped_copies(top_left);
+    assert_eq!(copies.len(), 3);
+    assert!(copies.contains(&Rect::new(790.0, 590.0, 50.0, 20.0)));
+}
+
+#[test]
+fn test_main_copy_is_the_most_visible() {
+    let outside = Rect::new(-60.0, -5.0, 50.0, 20.0);
+    assert_eq!(
+        space().main_copy(outside),
+        Rect::new(740.0, -5.0, 50.0, 20.0)
+    );
+    let barely_inside = Rect::new(-49.0, 100.0, 50.0, 20.0);
+    assert_eq!(space().main_copy(barely_inside), barely_inside);
+}
+
+#[test]
+fn test_normalize() {
+    assert_eq!(space().normalize(vec2(-10.0, 610.0)), vec2(790.0, 10.0));
+    assert_eq!(space().normalize(vec2(1610.0, -1190.0)), vec2(10.0, 10.0));
+    assert_eq!(space().normalize(vec2(400.0, 300.0)), vec2(400.0, 300.0));
+}
+
+#[test]
+fn test_shortest_wrapped_distance_and_direction() {
+    let space = space();
+    let (a, b) = (vec2(10.0, 300.0), vec2(790.0, 300.0));
+    assert_eq!(space.wrapped_delta(a, b), vec2(-20.0, 0.0));
+    assert_eq!(space.wrapped_distance(a, b), 20.0);
+    assert_eq!(space.wrapped_direction(a, b), vec2(-1.0, 0.0));
+    assert_eq!(space.wrapped_direction(b, a), vec2(1.0, 0.0));
+
+    let (c, d) = (vec2(10.0, 10.0), vec2(790.0, 590.0));
+    assert_eq!(space.wrapped_delta(c, d), vec2(-20.0, -20.0));
+    let (e, f) = (vec2(100.0, 100.0), vec2(300.0, 200.0));
+    assert_eq!(space.wrapped_delta(e, f), f - e);
+    assert_eq!(space.wrapped_direction(e, e), vec2(0.0, 0.0));
+}
+
+fn with(topology: Topology) -> TorusSpace {
+    TorusSpace::with_topology(vec2(800.0, 600.0), topology)
+}
+
+#[test]
+fn test_klein_bottle_mirrors_across_the_sides() {
+    let space = with(Topology::KleinBottle);
+    let left = Rect::new(-10.0, 100.0, 50.0, 20.0);
+    assert_eq!(
+        space.wrapped_copies(left),
+        vec![Rect::new(790.0, 480.0, 50.0, 20.0)]
+    );
+    let top = Rect::new(100.0, -10.0, 50.0, 20.0);
+    assert_eq!(
+        space.wrapped_copies(top),
+        vec![Rect::new(100.0, 590.0, 50.0, 20.0)]
+    );
+    assert_eq!(space.normalize(vec2(-10.0, 100.0)), vec2(790.0, 500.0));
+    assert_eq!(space.normalize(vec2(1610.0, 100.0)), vec2(10.0, 100.0));
+    assert_eq!(
+        space.wrapped_delta(vec2(10.0, 100.0), vec2(790.0, 500.0)),
+        vec2(-20.0, 0.0)
+    );
+}
+
+#[test]
+fn test_projective_plane_mirrors_across_every_edge() {
+    let space = with(Topology::ProjectivePlane);
+    let top = Rect::new(100.0, -10.0, 50.0, 20.0);
+    assert_eq!(
+        space.wrapped_copies(top),
+        vec![Rect::new(650.0, 590.0, 50.0, 20.0)]
+    );
+    assert_eq!(space.normalize(vec2(100.0, 610.0)), vec2(700.0, 10.0));
+}
+
+#[test]
+fn test_cylinder_has_walls_at_the_top_and_bottom() {
+    let space = with(Topology::Cylinder);
+    let top_left = Rect::new(-10.0, -10.0, 50.0, 20.0);
+    assert_eq!(
+        space.wrapped_copies(top_left),
+        vec![Rect::new(790.0, -10.0, 50.0, 20.0)]
+    );
+    let gone_up = Rect::new(100.0, -100.0, 50.0, 20.0);
+    assert_eq!(space.main_copy(gone_up), Rect::new(100.0, 0.0, 50.0, 20.0));
+    assert_eq!(space.normalize(vec2(-10.0, 700.0)), vec2(790.0, 600.0));
+    assert_eq!(
+        space.wrapped_delta(vec2(100.0, 10.0), vec2(100.0, 590.0)),
+        vec2(0.0, 580.0)
+    );
+}
+
+#[test]
+fn test_mirror_walls_keep_things_inside_and_reflect_them() {
+    let space = with(Topology::MirrorWalls);
+    let outside = Rect::new(-10.0, 590.0, 50.0, 20.0);
+    let confined = space.confine(outside);
+    assert_eq!(confined, Rect::new(0.0, 580.0, 50.0, 20.0));
+    assert!(space.wrapped_copies(confined).is_empty());
+    assert_eq!(space.main_copy(outside), confined);
+    assert_eq!(space.normalize(vec2(-10.0, 610.0)), vec2(10.0, 590.0));
+    let images = space.images(vec2(10.0, 300.0));
+    assert!(images.contains(&vec2(-10.0, 300.0)));
+    assert!(images.contains(&vec2(1590.0, 300.0)));
+}
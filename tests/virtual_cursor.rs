@@ -0,0 +1,132 @@
+use macroquad::input::KeyCode;
+use macroquad::math::{vec2, Rect, Vec2};
+use press_to_start::bot::BotAction::*;
+use press_to_start::bot::{run_bot, Bot, BotAction};
+use press_to_start::measure_text_headless;
+use press_to_start::stages::driver::{Shared, Simulation, StageGraph, StageId};
+use press_to_start::virtual_cursor::{clamp_to_rect, VirtualCursor};
+
+const BOUNDS: Rect = Rect {
+    x: 10.0,
+    y: 20.0,
+    w: 100.0,
+    h: 50.0,
+};
+
+#[test]
+fn test_accumulates_the_deltas_of_the_raw_mouse() {
+    let mut cursor = VirtualCursor::new(vec2(50.0, 40.0), BOUNDS);
+    cursor.accumulate(vec2(1000.0, 1000.0));
+    assert_eq!(cursor.pos(), vec2(50.0, 40.0));
+    cursor.accumulate(vec2(1010.0, 995.0));
+    assert_eq!(cursor.pos(), vec2(60.0, 35.0));
+    cursor.accumulate(vec2(1005.0, 1000.0));
+    assert_eq!(cursor.pos(), vec2(55.0, 40.0));
+}
+
+#[test]
+fn test_sensitivity_scales_the_deltas() {
+    let mut cursor = VirtualCursor::new(vec2(50.0, 40.0), BOUNDS);
+    cursor.sensitivity = 0.5;
+    cursor.accumulate(vec2(0.0, 0.0));
+    cursor.accumulate(vec2(20.0, -10.0));
+    assert_eq!(cursor.pos(), vec2(60.0, 35.0));
+    cursor.sensitivity = 2.0;
+    cursor.accumulate(vec2(15.0, -10.0));
+    assert_eq!(cursor.pos(), vec2(50.0, 35.0));
+}
+
+#[test]
+fn test_stays_inside_every_edge() {
+    let start = vec2(50.0, 40.0);
+    for (delta, expected) in [
+        (vec2(-500.0, 0.0), vec2(10.0, 40.0)),
+        (vec2(500.0, 0.0), vec2(110.0, 40.0)),
+        (vec2(0.0, -500.0), vec2(50.0, 20.0)),
+        (vec2(0.0, 500.0), vec2(50.0, 70.0)),
+    ] {
+        let mut cursor = VirtualCursor::new(start, BOUNDS);
+        cursor.accumulate(vec2(0.0, 0.0));
+        cursor.accumulate(delta);
+        assert_eq!(cursor.pos(), expected);
+        // coming back moves right away, instead of first undoing what went past the edge
+        cursor.accumulate(delta - delta.signum());
+        assert_eq!(cursor.pos(), expected - delta.signum());
+    }
+    assert_eq!(clamp_to_rect(vec2(-5.0, 500.0), BOUNDS), vec2(10.0, 70.0));
+    assert_eq!(
+        VirtualCursor::new(vec2(0.0, 0.0), BOUNDS).pos(),
+        vec2(10.0, 20.0)
+    );
+}
+
+#[test]
+fn test_shrinking_the_bounds_pulls_the_cursor_in() {
+    let mut cursor = VirtualCursor::new(vec2(100.0, 60.0), BOUNDS);
+    cursor.set_bounds(Rect::new(10.0, 20.0, 50.0, 25.0));
+    assert_eq!(cursor.pos(), vec2(60.0, 45.0));
+}
+
+#[test]
+fn test_resetting_the_reference_ignores_the_motion_while_paused() {
+    let mut cursor = VirtualCursor::new(vec2(50.0, 40.0), BOUNDS);
+    cursor.accumulate(vec2(0.0, 0.0));
+    cursor.accumulate(vec2(5.0, 0.0));
+    assert_eq!(cursor.pos(), vec2(55.0, 40.0));
+    // the menu was open and the free mouse went far away
+    cursor.reset_reference();
+    cursor.accumulate(vec2(400.0, 300.0));
+    assert_eq!(cursor.pos(), vec2(55.0, 40.0));
+    cursor.accumulate(vec2(402.0, 301.0));
+    assert_eq!(cursor.pos(), vec2(57.0, 41.0));
+}
+
+#[test]
+fn test_empty_bounds_keep_the_cursor_at_their_corner() {
+    let mut cursor = VirtualCursor::new(vec2(50.0, 40.0), BOUNDS);
+    cursor.set_bounds(Rect::new(10.0, 20.0, -5.0, -5.0));
+    assert_eq!(cursor.pos(), vec2(10.0, 20.0));
+    assert_eq!(
+        clamp_to_rect(vec2(0.0, 0.0), Rect::new(10.0, 20.0, 0.0, 0.0)),
+        vec2(10.0, 20.0)
+    );
+}
+
+fn prison_cursor_line(script: Vec<BotAction>) -> String {
+    prison_cursor_line_in(vec2(800.0, 600.0), script)
+}
+
+fn prison_cursor_line_in(screen: Vec2, script: Vec<BotAction>) -> String {
+    let mut bot = Bot::new(vec2(400.0, 300.0), script);
+    let mut graph = StageGraph::campaign();
+    graph.start = StageId::Prison;
+    let shared = Shared::new(screen, measure_text_headless, &bot.input());
+    let mut simulation = Simulation::new(graph, shared);
+    run_bot(&mut simulation, &mut bot, 1000);
+    let overlay = simulation.dev_overlay().unwrap();
+    let line = overlay
+        .lines
+        .iter()
+        .find(|line| line.starts_with("virtual cursor: "));
+    line.unwrap().clone()
+}
+
+#[test]
+fn test_prison_cursor_does_not_jump_after_the_pause_menu() {
+    let before = prison_cursor_line(vec![Press(KeyCode::F3), Wait(2)]);
+    let after = prison_cursor_line(vec![
+        Press(KeyCode::F3),
+        Wait(2),
+        Press(KeyCode::Escape),
+        MoveTo(vec2(20.0, 20.0)),
+        Press(KeyCode::Escape),
+        Wait(2),
+    ]);
+    assert_eq!(before, after);
+}
+
+#[test]
+fn test_prison_survives_a_minimized_window() {
+    let line = prison_cursor_line_in(Vec2::ZERO, vec![Press(KeyCode::F3), Wait(2)]);
+    assert!(line.starts_with("virtual cursor: "));
+}
@@ -0,0 +1,96 @@
+use press_to_start::steering::RocketKind;
+use press_to_start::waves::{Wave, WaveMixes, Waves, WAVE_BREAK_SECONDS};
+
+const DT: f32 = 0.1;
+
+/// Steps until the schedule asks for a launch, with `flying` rockets alive
+fn seconds_until_launch(waves: &mut Waves, flying: usize) -> f32 {
+    let mut time = 0.0;
+    while !waves.step(DT, flying) {
+        time += DT;
+        assert!(time < 100.0, "no launch");
+    }
+    time
+}
+
+#[test]
+fn test_waves_get_harder() {
+    let mixes = WaveMixes::embedded();
+    let first = Wave::number(1, &mixes);
+    assert_eq!(first.rockets, 1);
+    assert_eq!(first.speed, 1.0);
+    let mut previous = first;
+    for number in 2..20 {
+        let wave = Wave::number(number, &mixes);
+        assert!(wave.rockets >= previous.rockets);
+        assert!(wave.interval <= previous.interval);
+        assert!(wave.speed >= previous.speed);
+        assert!(wave.turn >= previous.turn);
+        previous = wave;
+    }
+    assert!(previous.interval > 0.0);
+}
+
+#[test]
+fn test_rockets_of_a_wave_launch_at_intervals() {
+    let mut waves = Waves::default();
+    assert!(waves.step(DT, 0));
+    seconds_until_launch(&mut waves, 0);
+    assert_eq!(waves.current().number, 2);
+    let interval = waves.current().interval;
+    let waited = seconds_until_launch(&mut waves, 1);
+    assert!((waited + DT - interval).abs() <= DT * 1.5);
+    assert!(!waves.step(DT, 2));
+}
+
+#[test]
+fn test_next_wave_waits_for_the_rockets_and_a_break() {
+    let mut waves = Waves::default();
+    assert!(waves.step(DT, 0));
+    for _ in 0..100 {
+        assert!(!waves.step(DT, 1));
+    }
+    assert!(!waves.in_break());
+    assert!(!waves.step(DT, 0));
+    assert!(waves.in_break());
+    let waited = seconds_until_launch(&mut waves, 0);
+    assert!((waited - WAVE_BREAK_SECONDS).abs() <= DT * 1.5);
+    assert_eq!(waves.current().number, 2);
+}
+
+#[test]
+fn test_waves_mix_rocket_kinds() {
+    let mixes = WaveMixes::embedded();
+    let first = Wave::number(1, &mixes);
+    assert_eq!(first.kinds, &[RocketKind::Homing]);
+    let later = Wave::number(10, &mixes);
+    assert!(later.kinds.len() > 1);
+    let kinds: Vec<RocketKind> = (0..later.kinds.len()).map(|i| later.kind(i)).collect();
+    assert_eq!(kinds, later.kinds);
+    assert_eq!(later.kind(later.kinds.len()), later.kind(0));
+}
+
+#[test]
+fn test_mixes_are_parsed_one_wave_per_line() {
+    let mixes = WaveMixes::parse("# comment\n\nhoming\n  decoy sine_wave  \n").unwrap();
+    assert_eq!(mixes.kinds(1), &[RocketKind::Homing]);
+    assert_eq!(mixes.kinds(2), &[RocketKind::Decoy, RocketKind::SineWave]);
+    assert_eq!(mixes.kinds(3), mixes.kinds(2));
+    assert_eq!(mixes.kinds(0), mixes.kinds(1));
+
+    let mut waves = Waves::new(mixes);
+    assert!(waves.step(DT, 0));
+    seconds_until_launch(&mut waves, 0);
+    assert_eq!(
+        waves.current().kinds,
+        &[RocketKind::Decoy, RocketKind::SineWave]
+    );
+}
+
+#[test]
+fn test_malformed_mixes_are_errors() {
+    let error = WaveMixes::parse("homing\nhoming rocket\n").unwrap_err();
+    assert!(error.starts_with("line 2:"), "{}", error);
+    assert!(error.contains("rocket"), "{}", error);
+    assert!(WaveMixes::parse("# only comments\n\n").is_err());
+}